@@ -0,0 +1,215 @@
+//! # ssh-agent 认证
+//! `auth: agent` 时通过 `SSH_AUTH_SOCK` 连接本机的 ssh-agent，列出其中的密钥，
+//! 连接空间服务器时由 ssh-agent 对认证请求签名，私钥不需要放在 rpush 的配置中。
+//!
+//! ssh-rs 本身只支持私钥文件，这里实现它的 [Signer]，见 vendor/ssh-rs 中的补丁。
+
+use std::env;
+use std::io::{self, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+
+use ssh_rs::error::SshError;
+use ssh_rs::{SshResult, Signer};
+
+/// ssh-agent 的 socket 路径所在的环境变量
+pub const SOCKET_ENV: &str = "SSH_AUTH_SOCK";
+
+// ssh-agent 协议的消息类型
+const SSH_AGENT_FAILURE: u8 = 5;
+const SSH_AGENTC_REQUEST_IDENTITIES: u8 = 11;
+const SSH_AGENT_IDENTITIES_ANSWER: u8 = 12;
+const SSH_AGENTC_SIGN_REQUEST: u8 = 13;
+const SSH_AGENT_SIGN_RESPONSE: u8 = 14;
+
+// RSA 密钥使用 rsa-sha2-256 签名，而不是已经被大多数服务器禁用的 ssh-rsa（SHA-1）
+const SSH_AGENT_RSA_SHA2_256: u32 = 2;
+
+/// ssh-agent 中的一个密钥
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identity {
+    /// 公钥
+    pub key_blob: Vec<u8>,
+    /// 注释，一般是密钥文件路径或者 `用户@主机`
+    pub comment: String,
+}
+
+impl Identity {
+    /// 密钥类型，例如 `ssh-ed25519`、`ssh-rsa`
+    pub fn key_type(&self) -> String {
+        Reader(&self.key_blob).string().map(|key_type| String::from_utf8_lossy(key_type).to_string()).unwrap_or_default()
+    }
+}
+
+/// 环境变量中 ssh-agent 的 socket 路径，没有运行 ssh-agent 时返回 None
+pub fn socket() -> Option<PathBuf> {
+    env::var_os(SOCKET_ENV).filter(|socket| !socket.is_empty()).map(PathBuf::from)
+}
+
+/// 列出 ssh-agent 中的密钥
+pub fn identities(socket: &Path) -> io::Result<Vec<Identity>> {
+    let answer = request(socket, SSH_AGENTC_REQUEST_IDENTITIES, &[])?;
+    let mut reader = Reader(&answer);
+    match reader.u8()? {
+        SSH_AGENT_IDENTITIES_ANSWER => {}
+        _ => return Err(invalid("ssh-agent 拒绝列出密钥")),
+    }
+    let count = reader.u32()?;
+    let mut identities = Vec::new();
+    for _ in 0..count {
+        let key_blob = reader.string()?.to_vec();
+        let comment = String::from_utf8_lossy(reader.string()?).to_string();
+        identities.push(Identity { key_blob, comment });
+    }
+    Ok(identities)
+}
+
+/// 让 ssh-agent 用密钥 `key_blob` 对 `data` 签名，返回签名（`string 算法, string 签名`）
+pub fn sign(socket: &Path, key_blob: &[u8], data: &[u8], flags: u32) -> io::Result<Vec<u8>> {
+    let mut payload = Vec::new();
+    put_string(&mut payload, key_blob);
+    put_string(&mut payload, data);
+    payload.extend_from_slice(&flags.to_be_bytes());
+    let response = request(socket, SSH_AGENTC_SIGN_REQUEST, &payload)?;
+    let mut reader = Reader(&response);
+    match reader.u8()? {
+        SSH_AGENT_SIGN_RESPONSE => Ok(reader.string()?.to_vec()),
+        SSH_AGENT_FAILURE => Err(invalid("ssh-agent 拒绝签名")),
+        _ => Err(invalid("ssh-agent 返回了无法识别的消息")),
+    }
+}
+
+/// 由 ssh-agent 签名的密钥，每次签名时重新连接 ssh-agent
+pub struct AgentSigner {
+    socket: PathBuf,
+    identity: Identity,
+}
+
+impl AgentSigner {
+    pub fn new(socket: &Path, identity: Identity) -> Self {
+        Self { socket: socket.to_path_buf(), identity }
+    }
+}
+
+impl Signer for AgentSigner {
+    fn algorithm(&self) -> String {
+        match self.identity.key_type().as_str() {
+            "ssh-rsa" => "rsa-sha2-256".to_string(),
+            key_type => key_type.to_string(),
+        }
+    }
+
+    fn public_key(&self) -> Vec<u8> {
+        self.identity.key_blob.clone()
+    }
+
+    fn sign(&self, data: &[u8]) -> SshResult<Vec<u8>> {
+        let flags = if self.identity.key_type() == "ssh-rsa" { SSH_AGENT_RSA_SHA2_256 } else { 0 };
+        sign(&self.socket, &self.identity.key_blob, data, flags).map_err(SshError::from)
+    }
+}
+
+/// 发送一个请求并读取回复，消息格式为 `uint32 长度, byte 类型, 内容`
+fn request(socket: &Path, message_type: u8, payload: &[u8]) -> io::Result<Vec<u8>> {
+    let mut stream = UnixStream::connect(socket)?;
+    let mut message = Vec::with_capacity(payload.len() + 5);
+    message.extend_from_slice(&(payload.len() as u32 + 1).to_be_bytes());
+    message.push(message_type);
+    message.extend_from_slice(payload);
+    stream.write_all(&message)?;
+
+    let mut len = [0; 4];
+    stream.read_exact(&mut len)?;
+    let mut response = vec![0; u32::from_be_bytes(len) as usize];
+    stream.read_exact(&mut response)?;
+    Ok(response)
+}
+
+fn put_string(buf: &mut Vec<u8>, value: &[u8]) {
+    buf.extend_from_slice(&(value.len() as u32).to_be_bytes());
+    buf.extend_from_slice(value);
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// 按 ssh 的数据格式依次读取
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        if self.0.len() < len {
+            return Err(invalid("ssh-agent 返回的数据不完整"));
+        }
+        let (value, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(value)
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn string(&mut self) -> io::Result<&'a [u8]> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+}
+
+#[test]
+fn test_agent_sign() {
+    use std::fs;
+    use std::process::{self, Command, Stdio};
+    use std::thread;
+    use std::time::Duration;
+
+    // 没有安装 OpenSSH 时跳过
+    if Command::new("ssh-agent").arg("-h").stderr(Stdio::null()).status().is_err() {
+        return;
+    }
+    let dir = env::temp_dir().join(format!("rpush-test-agent-{}", process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let socket = dir.join("agent.sock");
+    let key = dir.join("id_ed25519");
+    let status = Command::new("ssh-keygen")
+        .args(["-q", "-t", "ed25519", "-N", "", "-C", "rpush-test", "-f"]).arg(&key)
+        .status().unwrap();
+    assert!(status.success());
+    let mut agent = Command::new("ssh-agent").arg("-D").arg("-a").arg(&socket).stdout(Stdio::null()).spawn().unwrap();
+    for _ in 0..50 {
+        if socket.exists() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    let status = Command::new("ssh-add").arg(&key).env(SOCKET_ENV, &socket).stderr(Stdio::null()).status().unwrap();
+    assert!(status.success());
+
+    let identities = identities(&socket).unwrap();
+    assert_eq!(identities.len(), 1);
+    assert_eq!(identities[0].comment, "rpush-test");
+    assert_eq!(identities[0].key_type(), "ssh-ed25519");
+
+    let signer = AgentSigner::new(&socket, identities[0].clone());
+    assert_eq!(signer.algorithm(), "ssh-ed25519");
+    let signature = signer.sign(b"session id and auth request").unwrap();
+    let _ = agent.kill();
+    let _ = agent.wait();
+    fs::remove_dir_all(&dir).unwrap();
+
+    // 签名是 `string "ssh-ed25519", string 签名`，公钥是 `string "ssh-ed25519", string 公钥`
+    let mut reader = Reader(&signature);
+    assert_eq!(reader.string().unwrap(), b"ssh-ed25519");
+    let signature = reader.string().unwrap();
+    let mut reader = Reader(&identities[0].key_blob);
+    reader.string().unwrap();
+    let public_key = ring::signature::UnparsedPublicKey::new(&ring::signature::ED25519, reader.string().unwrap());
+    assert!(public_key.verify(b"session id and auth request", signature).is_ok());
+    assert!(public_key.verify(b"something else", signature).is_err());
+}
@@ -0,0 +1,551 @@
+//! # 打包压缩
+//! 把要推送的文件和目录打包成 tar 并压缩（gzip、zstd、xz 或者不压缩），打包时会跳过匹配排除规则的文件，并在写入的同时计算压缩包的 sha256。
+//!
+//! 读取文件和压缩都使用多个线程：多个线程按顺序预读文件内容；gzip 分块并行压缩，zstd 和 xz 使用各自的多线程模式。
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fmt::{Display, Formatter};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::mpsc::sync_channel;
+use std::thread::{self, JoinHandle};
+
+use flate2::{Compression, write::GzEncoder};
+use sha2::{Digest, Sha256};
+use xz2::stream::MtStreamBuilder;
+use xz2::write::XzEncoder;
+
+use crate::manifest::to_hex;
+use crate::remote::quote;
+use crate::utils as util;
+
+/// 压缩格式和压缩级别
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// gzip，级别 0-9
+    Gzip(u32),
+    /// zstd，级别 1-22
+    Zstd(i32),
+    /// xz，级别 0-9
+    Xz(u32),
+    /// 不压缩
+    Tar,
+}
+
+impl Default for Format {
+    fn default() -> Self {
+        Format::Gzip(9)
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    /// 格式为 `<名称>[:<级别>]`，例如 `gzip`、`gzip:6`、`zstd:19`、`xz`、`none`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, level) = match s.trim().split_once(':') {
+            Some((name, level)) => (name, Some(level)),
+            None => (s.trim(), None),
+        };
+        let invalid = || format!("无效的压缩格式：{}", s);
+        let parse_level = |default: i64, min: i64, max: i64| -> Result<i64, String> {
+            match level {
+                None => Ok(default),
+                Some(level) => level.parse::<i64>().ok().filter(|l| (min..=max).contains(l)).ok_or_else(invalid),
+            }
+        };
+        match name.to_lowercase().as_str() {
+            "gzip" | "gz" => Ok(Format::Gzip(parse_level(9, 0, 9)? as u32)),
+            "zstd" | "zst" => Ok(Format::Zstd(parse_level(3, 1, 22)? as i32)),
+            "xz" => Ok(Format::Xz(parse_level(6, 0, 9)? as u32)),
+            "none" | "tar" if level.is_none() => Ok(Format::Tar),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Format::Gzip(level) => write!(f, "gzip:{}", level),
+            Format::Zstd(level) => write!(f, "zstd:{}", level),
+            Format::Xz(level) => write!(f, "xz:{}", level),
+            Format::Tar => write!(f, "none"),
+        }
+    }
+}
+
+impl Format {
+    /// 压缩包的扩展名
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Gzip(_) => "tar.gz",
+            Format::Zstd(_) => "tar.zst",
+            Format::Xz(_) => "tar.xz",
+            Format::Tar => "tar",
+        }
+    }
+
+    /// 服务器上解压需要的命令
+    pub fn tools(&self) -> &'static [&'static str] {
+        match self {
+            Format::Gzip(_) => &["tar", "gzip"],
+            Format::Zstd(_) => &["tar", "zstd"],
+            Format::Xz(_) => &["tar", "xz"],
+            Format::Tar => &["tar"],
+        }
+    }
+
+    /// 检查服务器上是否有解压需要的命令
+    pub fn probe_command(&self) -> String {
+        self.tools().iter().map(|tool| format!("command -v {}", tool)).collect::<Vec<_>>().join(" && ")
+    }
+
+    /// 在当前目录解压 `file` 的命令
+    ///
+    /// 解压出的文件属于执行命令的用户，用 sudo 解压时也不会使用压缩包中本地用户的 uid
+    pub fn extract_command(&self, file: &str) -> String {
+        match self {
+            Format::Gzip(_) => format!("tar --no-same-owner -xzf {}", quote(file)),
+            Format::Zstd(_) => format!("zstd -dc {} | tar --no-same-owner -xf -", quote(file)),
+            Format::Xz(_) => format!("tar --no-same-owner -xJf {}", quote(file)),
+            Format::Tar => format!("tar --no-same-owner -xf {}", quote(file)),
+        }
+    }
+}
+
+/// 要打包的文件或目录
+#[derive(Debug, Clone)]
+pub struct Entry {
+    /// 压缩包中以 `/` 分隔的相对路径
+    pub path: String,
+    /// 本地路径
+    pub local: PathBuf,
+    pub is_dir: bool,
+    /// 文件大小，目录为 0
+    pub size: u64,
+    /// 打包时使用的权限，为 None 时使用本地文件的权限
+    pub mode: Option<u32>,
+}
+
+/// 收集目录中要打包的文件和目录，目录排在它的内容之前
+pub fn collect(dir: &Path, excludes: &[String]) -> io::Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    walk(dir, "", excludes, &mut entries)?;
+    Ok(entries)
+}
+
+/// 收集目录 `dir` 中的子目录 `sub` 里要打包的文件和目录，路径和排除规则都相对于 `dir`
+pub fn collect_sub(dir: &Path, sub: &str, excludes: &[String]) -> io::Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    walk(&dir.join(sub), &format!("{}/", sub), excludes, &mut entries)?;
+    Ok(entries)
+}
+
+/// 收集多个来源中要打包的文件和目录
+///
+/// 来源是目录时打包目录中的内容，是文件时以文件名打包。多个来源中的同名目录会合并，同名文件视为冲突。
+pub fn collect_sources(sources: &[PathBuf], excludes: &[String]) -> io::Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    let mut seen = BTreeMap::new();
+    for source in sources {
+        let metadata = fs::metadata(source)?;
+        let collected = if metadata.is_dir() {
+            collect(source, excludes)?
+        } else {
+            let name = source.file_name()
+                .ok_or_else(|| io::Error::other(format!("无效的文件名：{}", source.display())))?
+                .to_string_lossy()
+                .to_string();
+            if util::is_excluded(excludes, &name) {
+                continue;
+            }
+            vec![Entry { path: name, local: source.clone(), is_dir: false, size: metadata.len(), mode: None }]
+        };
+        for entry in collected {
+            match seen.get(&entry.path) {
+                Some(true) if entry.is_dir => continue,
+                Some(_) => return Err(io::Error::other(format!("多个来源包含相同的路径：{}", entry.path))),
+                None => {
+                    seen.insert(entry.path.clone(), entry.is_dir);
+                    entries.push(entry);
+                }
+            }
+        }
+    }
+    Ok(entries)
+}
+
+fn walk(dir: &Path, prefix: &str, excludes: &[String], entries: &mut Vec<Entry>) -> io::Result<()> {
+    let mut children = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+    children.sort_by_key(|child| child.file_name());
+    for child in children {
+        let path = format!("{}{}", prefix, child.file_name().to_string_lossy());
+        if util::is_excluded(excludes, &path) {
+            continue;
+        }
+        let metadata = fs::metadata(child.path())?;
+        if metadata.is_dir() {
+            entries.push(Entry { path: path.clone(), local: child.path(), is_dir: true, size: 0, mode: None });
+            walk(&child.path(), &format!("{}/", path), excludes, entries)?;
+        } else {
+            entries.push(Entry { path, local: child.path(), is_dir: false, size: metadata.len(), mode: None });
+        }
+    }
+    Ok(())
+}
+
+/// 统一设置打包时文件和目录的权限
+pub fn set_modes(entries: &mut [Entry], file_mode: Option<u32>, dir_mode: Option<u32>) {
+    for entry in entries {
+        entry.mode = if entry.is_dir { dir_mode } else { file_mode };
+    }
+}
+
+/// 只保留 `files` 中的文件，以及包含这些文件的目录
+pub fn retain_files(entries: &mut Vec<Entry>, files: &BTreeSet<PathBuf>) {
+    entries.retain(|entry| entry.is_dir || files.contains(&entry.local));
+    let dirs = entries.iter()
+        .filter(|entry| !entry.is_dir)
+        .flat_map(|entry| entry.path.match_indices('/').map(|(index, _)| entry.path[..index].to_string()))
+        .collect::<BTreeSet<_>>();
+    entries.retain(|entry| !entry.is_dir || dirs.contains(&entry.path));
+}
+
+// 预读时单个文件的最大大小，更大的文件打包时直接从磁盘读取
+const PREFETCH_FILE_SIZE: u64 = 1024 * 1024;
+// 每个预读线程最多缓存的文件数量
+const PREFETCH_COUNT: usize = 4;
+// gzip 并行压缩时每块的大小
+const GZIP_BLOCK_SIZE: usize = 1024 * 1024;
+
+/// 默认线程数，即 CPU 核数
+pub fn default_threads() -> usize {
+    thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
+
+/// 把 `entries` 打包压缩到 `target`，返回压缩包的 sha256
+///
+/// 每打包完一个文件或目录调用一次 `on_progress`，参数为它的大小
+pub fn build(entries: &[Entry], target: &Path, format: Format, threads: usize, on_progress: impl FnMut(u64)) -> io::Result<String> {
+    let threads = threads.max(1);
+    let file = HashWriter::new(File::create(target)?);
+    let mut file = match format {
+        Format::Gzip(level) => {
+            let enc = ParGzEncoder::new(file, Compression::new(level), threads);
+            write_tar(entries, threads, enc, on_progress)?.finish()?
+        }
+        Format::Zstd(level) => {
+            let mut enc = zstd::Encoder::new(file, level)?;
+            if threads > 1 {
+                enc.multithread(threads as u32)?;
+            }
+            write_tar(entries, threads, enc, on_progress)?.finish()?
+        }
+        Format::Xz(level) => {
+            let stream = MtStreamBuilder::new().threads(threads as u32).preset(level).encoder()?;
+            write_tar(entries, threads, XzEncoder::new_stream(file, stream), on_progress)?.finish()?
+        }
+        Format::Tar => write_tar(entries, threads, file, on_progress)?,
+    };
+    file.flush()?;
+    Ok(file.hash())
+}
+
+/// 预读的文件内容
+enum Prefetched {
+    /// 小文件，已经读入内存
+    Data(fs::Metadata, Vec<u8>),
+    /// 目录或者大文件，打包时再从磁盘读取
+    Path,
+}
+
+/// 把 `entries` 打包写入 `writer`，文件内容由 `threads` 个线程按顺序轮流预读
+fn write_tar<W: Write>(entries: &[Entry], threads: usize, writer: W, mut on_progress: impl FnMut(u64)) -> io::Result<W> {
+    thread::scope(|scope| {
+        let receivers = (0..threads)
+            .map(|worker| {
+                let (tx, rx) = sync_channel(PREFETCH_COUNT);
+                scope.spawn(move || {
+                    for entry in entries.iter().skip(worker).step_by(threads) {
+                        if tx.send(prefetch(entry)).is_err() {
+                            break;
+                        }
+                    }
+                });
+                rx
+            })
+            .collect::<Vec<_>>();
+
+        let mut tar = tar::Builder::new(writer);
+        for (index, entry) in entries.iter().enumerate() {
+            let prefetched = receivers[index % threads].recv()
+                .map_err(|_| io::Error::other("预读线程异常退出"))??;
+            match prefetched {
+                Prefetched::Data(metadata, data) => {
+                    let mut header = header(&metadata, entry.mode);
+                    header.set_size(data.len() as u64);
+                    tar.append_data(&mut header, &entry.path, data.as_slice())?;
+                }
+                Prefetched::Path => match entry.mode {
+                    Some(_) if entry.is_dir => {
+                        let mut header = header(&fs::metadata(&entry.local)?, entry.mode);
+                        tar.append_data(&mut header, &entry.path, io::empty())?;
+                    }
+                    Some(_) => {
+                        let file = File::open(&entry.local)?;
+                        let mut header = header(&file.metadata()?, entry.mode);
+                        tar.append_data(&mut header, &entry.path, file)?;
+                    }
+                    None => tar.append_path_with_name(&entry.local, &entry.path)?,
+                },
+            }
+            on_progress(entry.size);
+        }
+        tar.into_inner()
+    })
+}
+
+/// 根据文件信息生成 tar 头，`mode` 不为 None 时替换文件的权限
+fn header(metadata: &fs::Metadata, mode: Option<u32>) -> tar::Header {
+    let mut header = tar::Header::new_gnu();
+    header.set_metadata(metadata);
+    if let Some(mode) = mode {
+        header.set_mode(mode);
+    }
+    header
+}
+
+/// 预读一个文件
+fn prefetch(entry: &Entry) -> io::Result<Prefetched> {
+    if entry.is_dir {
+        return Ok(Prefetched::Path);
+    }
+    let metadata = fs::metadata(&entry.local)?;
+    if metadata.len() > PREFETCH_FILE_SIZE {
+        return Ok(Prefetched::Path);
+    }
+    Ok(Prefetched::Data(metadata, fs::read(&entry.local)?))
+}
+
+/// 分块并行压缩的 gzip 写入器
+///
+/// 每块单独压缩成一个 gzip member，多个 member 首尾相连仍然是合法的 gzip 文件，服务器上的 `tar` 可以直接解压。
+pub struct ParGzEncoder<W: Write> {
+    inner: W,
+    level: Compression,
+    threads: usize,
+    buf: Vec<u8>,
+    pending: VecDeque<JoinHandle<io::Result<Vec<u8>>>>,
+    submitted: bool,
+}
+
+impl<W: Write> ParGzEncoder<W> {
+    pub fn new(inner: W, level: Compression, threads: usize) -> Self {
+        Self {
+            inner,
+            level,
+            threads: threads.max(1),
+            buf: Vec::with_capacity(GZIP_BLOCK_SIZE),
+            pending: VecDeque::new(),
+            submitted: false,
+        }
+    }
+
+    /// 压缩剩余的数据，返回内部的写入器
+    pub fn finish(mut self) -> io::Result<W> {
+        if !self.buf.is_empty() || !self.submitted {
+            self.submit()?;
+        }
+        while !self.pending.is_empty() {
+            self.write_next()?;
+        }
+        Ok(self.inner)
+    }
+
+    /// 把缓冲区中的数据交给压缩线程，压缩中的块超过线程数时等待最早的一块完成
+    fn submit(&mut self) -> io::Result<()> {
+        let block = mem::replace(&mut self.buf, Vec::with_capacity(GZIP_BLOCK_SIZE));
+        let level = self.level;
+        self.pending.push_back(thread::spawn(move || {
+            let mut enc = GzEncoder::new(Vec::with_capacity(block.len() / 2), level);
+            enc.write_all(&block)?;
+            enc.finish()
+        }));
+        self.submitted = true;
+        while self.pending.len() > self.threads {
+            self.write_next()?;
+        }
+        Ok(())
+    }
+
+    /// 按顺序写入最早提交的一块
+    fn write_next(&mut self) -> io::Result<()> {
+        if let Some(handle) = self.pending.pop_front() {
+            let data = handle.join()
+                .map_err(|_| io::Error::other("压缩线程异常退出"))??;
+            self.inner.write_all(&data)?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for ParGzEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len().min(GZIP_BLOCK_SIZE - self.buf.len());
+        self.buf.extend_from_slice(&buf[..len]);
+        if self.buf.len() >= GZIP_BLOCK_SIZE {
+            self.submit()?;
+        }
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        while !self.pending.is_empty() {
+            self.write_next()?;
+        }
+        self.inner.flush()
+    }
+}
+
+/// 写入的同时计算 sha256
+pub struct HashWriter<W: Write> {
+    inner: W,
+    hasher: Sha256,
+}
+
+impl<W: Write> HashWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self { inner, hasher: Sha256::new() }
+    }
+
+    /// 已写入内容的 sha256
+    pub fn hash(self) -> String {
+        to_hex(&self.hasher.finalize())
+    }
+}
+
+impl<W: Write> Write for HashWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = self.inner.write(buf)?;
+        self.hasher.update(&buf[..len]);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[test]
+fn test_format_from_str() {
+    assert_eq!("gzip".parse(), Ok(Format::Gzip(9)));
+    assert_eq!("gzip:1".parse(), Ok(Format::Gzip(1)));
+    assert_eq!("zstd:19".parse(), Ok(Format::Zstd(19)));
+    assert_eq!("XZ".parse(), Ok(Format::Xz(6)));
+    assert_eq!("none".parse(), Ok(Format::Tar));
+    assert!("gzip:10".parse::<Format>().is_err());
+    assert!("bzip2".parse::<Format>().is_err());
+    assert_eq!(Format::Zstd(19).to_string().parse(), Ok(Format::Zstd(19)));
+}
+
+#[test]
+fn test_par_gz_encoder() {
+    use std::io::Read;
+
+    let data = (0..GZIP_BLOCK_SIZE * 3 + 100).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+    let mut enc = ParGzEncoder::new(Vec::new(), Compression::fast(), 4);
+    enc.write_all(&data).unwrap();
+    let compressed = enc.finish().unwrap();
+
+    let mut decompressed = Vec::new();
+    flate2::read::MultiGzDecoder::new(compressed.as_slice()).read_to_end(&mut decompressed).unwrap();
+    assert_eq!(decompressed, data);
+
+    let empty = ParGzEncoder::new(Vec::new(), Compression::fast(), 4).finish().unwrap();
+    assert!(!empty.is_empty());
+}
+
+#[test]
+fn test_hash_writer() {
+    let mut writer = HashWriter::new(Vec::new());
+    writer.write_all(b"hello").unwrap();
+    assert_eq!(writer.inner, b"hello");
+    assert_eq!(writer.hash(), "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824");
+}
+
+#[test]
+fn test_collect_sources() {
+    use std::env;
+    use std::process;
+
+    let root = env::temp_dir().join(format!("rpush-test-sources-{}", process::id()));
+    fs::create_dir_all(root.join("a/js")).unwrap();
+    fs::create_dir_all(root.join("b/js")).unwrap();
+    fs::write(root.join("a/js/app.js"), b"app").unwrap();
+    fs::write(root.join("b/js/lib.js"), b"lib").unwrap();
+    fs::write(root.join("robots.txt"), b"robots").unwrap();
+
+    let sources = vec![root.join("a"), root.join("b"), root.join("robots.txt")];
+    let paths = collect_sources(&sources, &[]).unwrap().into_iter().map(|entry| entry.path).collect::<Vec<_>>();
+    let conflict = collect_sources(&[root.join("a"), root.join("a")], &[]);
+    fs::remove_dir_all(&root).unwrap();
+
+    assert_eq!(paths, vec!["js", "js/app.js", "js/lib.js", "robots.txt"]);
+    assert!(conflict.is_err());
+}
+
+#[test]
+fn test_retain_files() {
+    let entry = |path: &str, is_dir: bool| Entry {
+        path: path.to_string(),
+        local: Path::new("/site").join(path),
+        is_dir,
+        size: 0,
+        mode: None,
+    };
+    let mut entries = vec![
+        entry("css", true),
+        entry("css/app.css", false),
+        entry("node_modules", true),
+        entry("node_modules/lib.js", false),
+        entry("index.html", false),
+        entry(".env", false),
+    ];
+    let files = ["css/app.css", "index.html"].iter().map(|path| Path::new("/site").join(path)).collect();
+    retain_files(&mut entries, &files);
+    let paths = entries.into_iter().map(|entry| entry.path).collect::<Vec<_>>();
+    assert_eq!(paths, vec!["css", "css/app.css", "index.html"]);
+}
+
+#[test]
+fn test_build_with_modes() {
+    use std::env;
+    use std::process;
+
+    let root = env::temp_dir().join(format!("rpush-test-modes-{}", process::id()));
+    fs::create_dir_all(root.join("site/js")).unwrap();
+    fs::write(root.join("site/js/app.js"), b"app").unwrap();
+    fs::write(root.join("site/big.bin"), vec![0u8; PREFETCH_FILE_SIZE as usize + 1]).unwrap();
+
+    let mut entries = collect(&root.join("site"), &[]).unwrap();
+    set_modes(&mut entries, Some(0o640), Some(0o750));
+    let target = root.join("site.tar");
+    build(&entries, &target, Format::Tar, 2, |_| {}).unwrap();
+
+    let mut modes = Vec::new();
+    for entry in tar::Archive::new(File::open(&target).unwrap()).entries().unwrap() {
+        let entry = entry.unwrap();
+        modes.push((entry.path().unwrap().to_string_lossy().to_string(), entry.header().mode().unwrap()));
+    }
+    fs::remove_dir_all(&root).unwrap();
+
+    assert_eq!(modes, vec![
+        ("big.bin".to_string(), 0o640),
+        ("js".to_string(), 0o750),
+        ("js/app.js".to_string(), 0o640),
+    ]);
+}
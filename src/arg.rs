@@ -11,18 +11,49 @@ pub fn get_matches() -> ArgMatches {
         .arg_required_else_help(true)
         // 添加服务器空间配置
         .subcommand(Command::new("add")
-            .about("Add server space config"))
+            .about("Add server space config")
+            .arg(Arg::new("from_ssh_config")
+                .long("from-ssh-config")
+                .value_parser(value_parser!(String))
+                .action(ArgAction::Set)
+                .value_name("alias")
+                .help("take host, port, user, identity file and jump hosts from a Host in ~/.ssh/config")))
+        // 从 ~/.ssh/config 批量导入服务器空间配置
+        .subcommand(Command::new("import-ssh-config")
+            .about("Import hosts in ~/.ssh/config as server spaces, named after their aliases")
+            .arg(Arg::new("aliases")
+                .value_parser(value_parser!(String))
+                .action(ArgAction::Append)
+                .num_args(0..)
+                .help("host aliases to import, defaults to all hosts without wildcards"))
+            .arg(Arg::new("path")
+                .long("path")
+                .value_parser(value_parser!(String))
+                .action(ArgAction::Set)
+                .required(true)
+                .help("target path of the imported spaces")))
         // 列出服务器空间配置
         .subcommand(Command::new("list").
-            about("List server space config"))
+            about("List server space config")
+            .arg(Arg::new("filter")
+                .long("filter")
+                .value_parser(value_parser!(String))
+                .action(ArgAction::Set)
+                .help("only list spaces with this tag or whose host contains this text"))
+            .arg(format_arg()))
         // 查看服务器空间详情
         .subcommand(Command::new("detail")
-            .about("Print server space config detail")
+            .about("Print server space config detail, passwords are hidden")
             .arg(Arg::new("space_name")
                 .value_parser(value_parser!(String))
                 .action(ArgAction::Set)
                 .required(true)
             )
+            .arg(Arg::new("show_secret")
+                .long("show-secret")
+                .action(ArgAction::SetTrue)
+                .help("print the plain passwords after confirmation"))
+            .arg(format_arg())
         )
         // 移除服务器空间配置
         .subcommand(Command::new("remove")
@@ -36,16 +67,74 @@ pub fn get_matches() -> ArgMatches {
         )
         // 推送当前目录文件到服务器空间
         .subcommand(Command::new("push")
-            .about("Push the specified directory under the current directory to the server")
-            .arg(Arg::new("pushed_dir")
+            .about("Push local files or directories to the server")
+            .arg(Arg::new("sources")
                 .value_parser(value_parser!(String))
-                .action(ArgAction::Set)
+                .action(ArgAction::Append)
+                .num_args(1..)
                 .required(true)
-                .help("to be pushed dir"))
+                .help("files or dirs to be pushed, the contents of a dir are pushed"))
             .arg(Arg::new("space_name")
                 .value_parser(value_parser!(String))
                 .action(ArgAction::Set)
-                .required(true)))
+                .required(true))
+            .arg(Arg::new("dest")
+                .long("dest")
+                .value_parser(value_parser!(String))
+                .action(ArgAction::Set)
+                .help("push into this subdirectory of the space path"))
+            .arg(Arg::new("create")
+                .long("create")
+                .action(ArgAction::SetTrue)
+                .help("create the space path with mkdir -p if it does not exist"))
+            .arg(Arg::new("delete")
+                .long("delete")
+                .action(ArgAction::SetTrue)
+                .help("delete remote files that no longer exist locally"))
+            .arg(Arg::new("exclude")
+                .long("exclude")
+                .value_parser(value_parser!(String))
+                .action(ArgAction::Append)
+                .help("exclude files matching the pattern, e.g. *.map"))
+            .arg(Arg::new("protect")
+                .long("protect")
+                .value_parser(value_parser!(String))
+                .action(ArgAction::Append)
+                .help("never delete remote paths matching the pattern, e.g. uploads/"))
+            .arg(Arg::new("retries")
+                .long("retries")
+                .value_parser(value_parser!(u32))
+                .action(ArgAction::Set)
+                .default_value("3")
+                .help("retry times when the upload fails, uploaded parts are resumed"))
+            .arg(Arg::new("verify")
+                .long("verify")
+                .action(ArgAction::SetTrue)
+                .help("verify the sha256 of every file after extraction"))
+            .arg(Arg::new("compression")
+                .long("compression")
+                .value_parser(value_parser!(String))
+                .action(ArgAction::Set)
+                .help("compression format and level: gzip[:0-9], zstd[:1-22], xz[:0-9] or none"))
+            .arg(Arg::new("threads")
+                .long("threads")
+                .value_parser(value_parser!(usize))
+                .action(ArgAction::Set)
+                .help("threads used to read and compress files, defaults to the number of CPUs"))
+            .arg(Arg::new("git")
+                .long("git")
+                .value_name("REV")
+                .value_parser(value_parser!(String))
+                .action(ArgAction::Set)
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("HEAD")
+                .help("push only files tracked by git at HEAD, or at --git=<rev>"))
+            .arg(Arg::new("allow_dirty")
+                .long("allow-dirty")
+                .action(ArgAction::SetTrue)
+                .requires("git")
+                .help("push with --git even if tracked files have uncommitted changes")))
         .subcommand(Command::new("rmrf")
             .about("Delete all dirs and files in the specified server space")
             .arg(Arg::new("space_name")
@@ -53,5 +142,152 @@ pub fn get_matches() -> ArgMatches {
                 .action(ArgAction::Set)
                 .required(true)
                 .help("server space name")))
+        // 比较本地目录和服务器空间的差异
+        .subcommand(Command::new("diff")
+            .about("Show the differences between a local directory and the server space")
+            .arg(Arg::new("local_dir")
+                .value_parser(value_parser!(String))
+                .action(ArgAction::Set)
+                .required(true)
+                .help("local dir to compare"))
+            .arg(Arg::new("space_name")
+                .value_parser(value_parser!(String))
+                .action(ArgAction::Set)
+                .required(true)
+                .help("server space name"))
+            .arg(Arg::new("dest")
+                .long("dest")
+                .value_parser(value_parser!(String))
+                .action(ArgAction::Set)
+                .help("compare with this subdirectory of the space path, as written by push --dest"))
+            .arg(Arg::new("content")
+                .long("content")
+                .action(ArgAction::SetTrue)
+                .help("show unified diffs of modified text files")))
+        // 列出空间的备份
+        .subcommand(Command::new("backups")
+            .about("List the backups of the server space")
+            .arg(Arg::new("space_name")
+                .value_parser(value_parser!(String))
+                .action(ArgAction::Set)
+                .required(true)
+                .help("server space name")))
+        // 从备份恢复空间
+        .subcommand(Command::new("restore")
+            .about("Restore the server space from a backup")
+            .arg(Arg::new("space_name")
+                .value_parser(value_parser!(String))
+                .action(ArgAction::Set)
+                .required(true)
+                .help("server space name"))
+            .arg(Arg::new("backup_id")
+                .value_parser(value_parser!(String))
+                .action(ArgAction::Set)
+                .required(true)
+                .help("backup id, see `rpush backups <space_name>`")))
+        // 为服务器空间添加跳板机
+        .subcommand(Command::new("add-jump")
+            .about("Add a jump host to a server space, connections go through jump hosts in order")
+            .arg(Arg::new("space_name")
+                .value_parser(value_parser!(String))
+                .action(ArgAction::Set)
+                .required(true)
+                .help("server space name")))
+        // 监听本地目录，变化后自动推送
+        .subcommand(Command::new("watch")
+            .about("Watch a local dir and push changed files to the server space incrementally")
+            .arg(Arg::new("dir")
+                .value_parser(value_parser!(String))
+                .action(ArgAction::Set)
+                .required(true)
+                .help("local dir to watch, its contents are pushed"))
+            .arg(Arg::new("space_name")
+                .value_parser(value_parser!(String))
+                .action(ArgAction::Set)
+                .required(true))
+            .arg(Arg::new("dest")
+                .long("dest")
+                .value_parser(value_parser!(String))
+                .action(ArgAction::Set)
+                .help("push into this subdirectory of the space path"))
+            .arg(Arg::new("delete")
+                .long("delete")
+                .action(ArgAction::SetTrue)
+                .help("delete remote files when they are deleted locally"))
+            .arg(Arg::new("exclude")
+                .long("exclude")
+                .value_parser(value_parser!(String))
+                .action(ArgAction::Append)
+                .help("exclude files matching the pattern, e.g. *.map"))
+            .arg(Arg::new("protect")
+                .long("protect")
+                .value_parser(value_parser!(String))
+                .action(ArgAction::Append)
+                .help("never delete remote paths matching the pattern, e.g. uploads/"))
+            .arg(Arg::new("debounce")
+                .long("debounce")
+                .value_parser(value_parser!(u64))
+                .action(ArgAction::Set)
+                .default_value("500")
+                .help("milliseconds without further changes before pushing"))
+            .arg(Arg::new("retries")
+                .long("retries")
+                .value_parser(value_parser!(u32))
+                .action(ArgAction::Set)
+                .default_value("3")
+                .help("retry times when the upload fails, uploaded parts are resumed")))
+        // 查看服务器上部署的版本
+        .subcommand(Command::new("status")
+            .about("Show the revision deployed in the server space, read from .rpush-revision")
+            .arg(Arg::new("space_name")
+                .value_parser(value_parser!(String))
+                .action(ArgAction::Set)
+                .required(true)
+                .help("server space name"))
+            .arg(Arg::new("dest")
+                .long("dest")
+                .value_parser(value_parser!(String))
+                .action(ArgAction::Set)
+                .help("read the revision of this subdirectory of the space path"))
+            .arg(Arg::new("json")
+                .long("json")
+                .action(ArgAction::SetTrue)
+                .help("print the revision as JSON")))
+        // 查询操作历史
+        .subcommand(Command::new("history")
+            .about("Show the local history of push, rmrf and restore, newest first")
+            .arg(Arg::new("space_name")
+                .value_parser(value_parser!(String))
+                .action(ArgAction::Set)
+                .help("only show the history of this server space"))
+            .arg(Arg::new("limit")
+                .long("limit")
+                .value_parser(value_parser!(usize))
+                .action(ArgAction::Set)
+                .default_value("20")
+                .help("max number of entries to show"))
+            .arg(Arg::new("json")
+                .long("json")
+                .action(ArgAction::SetTrue)
+                .help("print the entries as a JSON array")))
+        // 测试空间配置
+        .subcommand(Command::new("test")
+            .visible_alias("doctor")
+            .about("Check DNS, connection, authentication, path and remote tools of a server space")
+            .arg(Arg::new("space_name")
+                .value_parser(value_parser!(String))
+                .action(ArgAction::Set)
+                .required(true)
+                .help("server space name")))
         .get_matches()
-}
\ No newline at end of file
+}
+
+/// `list`、`detail` 的输出格式参数
+fn format_arg() -> Arg {
+    Arg::new("format")
+        .long("format")
+        .value_parser(["table", "json", "yaml"])
+        .default_value("table")
+        .action(ArgAction::Set)
+        .help("output format")
+}
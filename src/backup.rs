@@ -0,0 +1,136 @@
+//! # 空间备份
+//! 推送前把空间中的文件打包备份到 `path/.rpush-backups/<时间戳>.tar.gz`，需要时可以从备份恢复。
+
+use crate::remote::{join, quote};
+
+/// 备份目录名称
+pub const BACKUP_DIR: &str = ".rpush-backups";
+
+/// 默认保留的备份数量
+pub const DEFAULT_KEEP: usize = 5;
+
+/// 一个备份
+#[derive(Debug, PartialEq, Eq)]
+pub struct Backup {
+    /// 备份 id，即时间戳，例如 `20230301123000`；同一秒内的多个备份加上序号，例如 `20230301123000-01`
+    pub id: String,
+    /// 备份文件大小（字节）
+    pub size: u64,
+}
+
+/// 备份 id 是否有效：14 位时间戳，可以带有 `-序号`，其他输入不能用来拼接备份文件的路径
+pub fn is_valid_id(id: &str) -> bool {
+    let (timestamp, seq) = match id.split_once('-') {
+        Some((timestamp, seq)) => (timestamp, Some(seq)),
+        None => (id, None),
+    };
+    let is_digits = |text: &str| !text.is_empty() && text.bytes().all(|byte| byte.is_ascii_digit());
+    timestamp.len() == 14 && is_digits(timestamp) && seq.is_none_or(is_digits)
+}
+
+/// 判断相对路径是否在备份目录中
+pub fn is_backup_path(path: &str) -> bool {
+    path == BACKUP_DIR || path.starts_with(&format!("{}/", BACKUP_DIR))
+}
+
+/// 创建备份并清理超出保留数量的旧备份的命令，输出新备份的 id
+///
+/// 先用 `set -C`（noclobber）占用备份文件名，同一秒内已经有备份时加上序号，不会覆盖已有的备份
+pub fn create_command(path: &str, keep: usize) -> String {
+    format!(
+        "cd {} && mkdir -p {dir} && ts=$(date +%Y%m%d%H%M%S) && id=$ts && n=0 && \
+         while ! (set -C; : > {dir}/$id.tar.gz) 2>/dev/null; do \
+         n=$((n + 1)); [ $n -le 99 ] || exit 1; id=$ts-$(printf %02d $n); done && \
+         {{ tar czf {dir}/$id.tar.gz --exclude=./{dir} . || {{ rm -f {dir}/$id.tar.gz; exit 1; }}; }} && echo $id && {}",
+        quote(path),
+        prune_command(keep),
+        dir = BACKUP_DIR,
+    )
+}
+
+/// 估算备份大小的命令，输出空间中除备份目录以外的文件占用的空间（KiB），按未压缩的大小估算
+pub fn size_command(path: &str) -> String {
+    format!("cd {} && du -sk --exclude=./{dir} . | cut -f1", quote(path), dir = BACKUP_DIR)
+}
+
+/// 删除超出保留数量的旧备份的命令，在 [create_command] 中刚创建备份 `$id` 之后执行
+///
+/// 刚创建的备份总是保留；其他备份按 id 排序而不是按文件名，`<时间戳>.tar.gz` 要排在 `<时间戳>-01.tar.gz` 之前
+fn prune_command(keep: usize) -> String {
+    format!(
+        "ls -1 {dir} | sed -n 's/\\.tar\\.gz$//p' | grep -vxF \"$id\" | LC_ALL=C sort -r | tail -n +{} | sed 's#^\\(.*\\)$#{dir}/\\1.tar.gz#' | xargs -r rm -f --",
+        keep.max(1),
+        dir = BACKUP_DIR,
+    )
+}
+
+/// 列出备份的命令，每行输出 `<文件名> <大小>`
+pub fn list_command(path: &str) -> String {
+    format!(
+        "cd {} && if [ -d {dir} ]; then cd {dir} && for f in *.tar.gz; do [ -f \"$f\" ] && echo \"$f $(stat -c %s \"$f\")\"; done; fi; true",
+        quote(path),
+        dir = BACKUP_DIR,
+    )
+}
+
+/// 解析 [list_command] 的输出，按时间从新到旧排列
+pub fn parse_list(output: &str) -> Vec<Backup> {
+    let mut backups = output.lines()
+        .filter_map(|line| line.split_once(' '))
+        .filter_map(|(name, size)| {
+            Some(Backup {
+                id: name.strip_suffix(".tar.gz")?.to_string(),
+                size: size.trim().parse().ok()?,
+            })
+        })
+        .collect::<Vec<_>>();
+    backups.sort_by(|a, b| b.id.cmp(&a.id));
+    backups
+}
+
+/// 从备份恢复的命令：先完整读取一遍备份确认没有损坏，再清空空间中除备份目录以外的文件，最后解压备份
+///
+/// `id` 需要先用 [is_valid_id] 检查
+pub fn restore_command(path: &str, id: &str) -> String {
+    let backup_file = join(BACKUP_DIR, &format!("{}.tar.gz", id));
+    format!(
+        "cd {} && test -f {file} && tar tzf {file} > /dev/null && find . -mindepth 1 -maxdepth 1 ! -name {dir} -exec rm -rf {{}} + && tar xzf {file}",
+        quote(path),
+        file = quote(&backup_file),
+        dir = BACKUP_DIR,
+    )
+}
+
+#[test]
+fn test_parse_list() {
+    let output = "20230301120000.tar.gz 1024\n20230302120000.tar.gz 2048\n20230302120000-01.tar.gz 4096\nbroken\n";
+    assert_eq!(parse_list(output), vec![
+        Backup { id: "20230302120000-01".to_string(), size: 4096 },
+        Backup { id: "20230302120000".to_string(), size: 2048 },
+        Backup { id: "20230301120000".to_string(), size: 1024 },
+    ]);
+}
+
+#[test]
+fn test_restore_command() {
+    let command = restore_command("/var/www", "20230301120000-01");
+    let check = command.find("tar tzf '.rpush-backups/20230301120000-01.tar.gz' > /dev/null").unwrap();
+    assert!(check < command.find("rm -rf").unwrap());
+}
+
+#[test]
+fn test_is_valid_id() {
+    assert!(is_valid_id("20230301120000"));
+    assert!(is_valid_id("20230301120000-01"));
+    assert!(!is_valid_id("../../etc/x"));
+    assert!(!is_valid_id("20230301120000/../../x"));
+    assert!(!is_valid_id("20230301120000-"));
+    assert!(!is_valid_id("2023030112000"));
+    assert!(!is_valid_id(""));
+}
+
+#[test]
+fn test_is_backup_path() {
+    assert!(is_backup_path(".rpush-backups/20230301120000.tar.gz"));
+    assert!(!is_backup_path(".rpush-backups.html"));
+}
@@ -8,16 +8,72 @@ use std::fmt::{Display, Formatter};
 use nu_ansi_term::Color::Green;
 use serde::{Deserialize, Serialize};
 
+use crate::backup;
+use crate::credential;
+
 // 配置文件名
 const CONFIG_FILE_NAME: &str = ".rpush_config";
+// 输出时代替密码
+const REDACTED: &str = "******";
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerSpace {
     pub name: String,
     pub host: String,
     pub path: String,
     pub user: String,
     pub pass: String,
+    /// 认证方式，`prompt` 时不保存密码，连接时输入
+    #[serde(default)]
+    pub auth: Auth,
+    /// 私钥文件，例如 `~/.ssh/id_ed25519`，配置后优先使用私钥认证，失败时再使用密码
+    #[serde(default)]
+    pub identity_file: Option<String>,
+    /// ssh 端口
+    #[serde(default = "default_port")]
+    pub port: u16,
+    /// 跳板机，按连接顺序排列
+    #[serde(default)]
+    pub jump_hosts: Vec<JumpHost>,
+    /// 推送时排除的文件规则
+    #[serde(default)]
+    pub excludes: Vec<String>,
+    /// `push --delete` 时不会被删除的远程路径规则，例如 `uploads/`
+    #[serde(default)]
+    pub protected: Vec<String>,
+    /// 推送前是否备份空间中的文件
+    #[serde(default)]
+    pub backup_before_push: bool,
+    /// 最多保留的备份数量
+    #[serde(default = "default_backup_keep")]
+    pub backup_keep: usize,
+    /// 压缩格式，例如 `gzip:9`、`zstd:3`、`xz`、`none`，默认 `gzip:9`
+    #[serde(default)]
+    pub compression: Option<String>,
+    /// 解压后文件和目录的所有者，例如 `www-data`
+    #[serde(default)]
+    pub owner: Option<String>,
+    /// 解压后文件和目录的用户组
+    #[serde(default)]
+    pub group: Option<String>,
+    /// 文件权限（八进制），例如 `0644`
+    #[serde(default)]
+    pub file_mode: Option<String>,
+    /// 目录权限（八进制），例如 `0755`
+    #[serde(default)]
+    pub dir_mode: Option<String>,
+    /// 是否使用 sudo：压缩包上传到用户自己的目录，再用 sudo 解压到空间路径并修改所有者和权限
+    #[serde(default, alias = "sudo")]
+    pub use_sudo: bool,
+    /// 连接服务器使用的代理，例如 `socks5://127.0.0.1:1080`，为空时使用全局代理
+    #[serde(default)]
+    pub proxy: Option<String>,
+    /// 标签，`list --filter` 可以按标签筛选
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// 最后一次成功推送的信息
+    #[serde(default)]
+    pub last_push: Option<LastPush>,
 }
 
 impl ServerSpace {
@@ -28,27 +84,125 @@ impl ServerSpace {
             path: String::from(path),
             user: String::from(user),
             pass: String::from(pass),
+            auth: Auth::default(),
+            identity_file: None,
+            port: default_port(),
+            jump_hosts: Vec::new(),
+            excludes: Vec::new(),
+            protected: Vec::new(),
+            backup_before_push: false,
+            backup_keep: default_backup_keep(),
+            compression: None,
+            owner: None,
+            group: None,
+            file_mode: None,
+            dir_mode: None,
+            use_sudo: false,
+            proxy: None,
+            tags: Vec::new(),
+            last_push: None,
         }
     }
 }
 
+/// 最后一次成功推送的信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LastPush {
+    /// 推送完成的时间，unix 时间戳（秒）
+    pub time: u64,
+    /// 推送的本地文件或目录
+    pub sources: Vec<String>,
+    /// 推送到的服务器目录
+    pub remote_dir: String,
+    /// 推送的文件数量
+    pub files: usize,
+    /// 推送的文件总大小（字节）
+    pub bytes: u64,
+}
+
+/// 认证方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Auth {
+    /// 使用配置文件中加密保存的密码
+    #[default]
+    Password,
+    /// 不保存密码，每次运行时输入，同一次运行中只输入一次
+    Prompt,
+    /// 使用 `SSH_AUTH_SOCK` 中的 ssh-agent 签名，不保存密码
+    Agent,
+}
+
+/// 跳板机
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JumpHost {
+    pub host: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    pub user: String,
+    /// 加密后的密码，为空时使用 ssh-agent 或私钥认证，都没有时连接时输入
+    #[serde(default)]
+    pub pass: String,
+    /// 私钥文件
+    #[serde(default)]
+    pub identity_file: Option<String>,
+}
+
+fn default_port() -> u16 {
+    22
+}
+
+fn default_backup_keep() -> usize {
+    backup::DEFAULT_KEEP
+}
+
 impl Display for ServerSpace {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let pass = match self.auth {
+            Auth::Prompt => "（连接时输入）".to_string(),
+            Auth::Agent => "（使用 ssh-agent）".to_string(),
+            Auth::Password => redact(&self.pass),
+        };
         write!(f, "空间名称：{}\n主机地址：{}\n目标路径：{}\n用户名：{}\n密码：{}",
                Green.paint(&self.name), Green.paint(&self.host), Green.paint(&self.path),
-        Green.paint(&self.user), Green.paint(&self.pass))
+        Green.paint(&self.user), Green.paint(pass))
+    }
+}
+
+impl ServerSpace {
+    /// 隐藏密码后的配置，用于输出，`env:`、`cmd:` 来源本身不是密码，原样保留
+    pub fn redacted(&self) -> Self {
+        let mut server_space = self.clone();
+        server_space.pass = redact(&self.pass);
+        for jump in &mut server_space.jump_hosts {
+            jump.pass = redact(&jump.pass);
+        }
+        server_space
+    }
+}
+
+/// 隐藏加密保存的密码
+fn redact(pass: &str) -> String {
+    if pass.is_empty() || credential::is_source(pass) {
+        pass.to_string()
+    } else {
+        REDACTED.to_string()
     }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Config {
     server_space_list: HashMap<String, ServerSpace>,
+    /// 全局代理，空间没有配置代理时使用
+    #[serde(default)]
+    proxy: Option<String>,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
-            server_space_list: HashMap::new()
+            server_space_list: HashMap::new(),
+            proxy: None,
         }
     }
 }
@@ -68,31 +222,44 @@ impl Config {
         }
     }
 
-    pub fn list_server_space() -> Vec<String> {
-        let cfg = get_config();
-        let server_space_list = &cfg.server_space_list;
-        server_space_list.values()
-            .into_iter()
-            .map(|server_space| server_space.name.clone())
-            .collect::<Vec<String>>()
+    pub fn update_server_space(server_space: ServerSpace) -> Result<(), &'static str> {
+        let mut cfg = get_config();
+        let server_space_list = &mut cfg.server_space_list;
+
+        match server_space_list.get_mut(&server_space.name) {
+            Some(existed) => {
+                *existed = server_space;
+                save_config(cfg);
+                Ok(())
+            }
+            None => Err("空间不存在"),
+        }
+    }
+
+    /// 所有空间，按名称排序
+    pub fn server_spaces() -> Vec<ServerSpace> {
+        let mut server_spaces = get_config().server_space_list.into_values().collect::<Vec<_>>();
+        server_spaces.sort_by(|a, b| a.name.cmp(&b.name));
+        server_spaces
+    }
+
+    /// 记录空间最后一次成功推送的信息
+    pub fn record_push(server_space_name: &str, last_push: LastPush) -> Result<(), &'static str> {
+        let mut cfg = get_config();
+        match cfg.server_space_list.get_mut(server_space_name) {
+            Some(server_space) => {
+                server_space.last_push = Some(last_push);
+                save_config(cfg);
+                Ok(())
+            }
+            None => Err("空间不存在"),
+        }
     }
 
     pub fn server_space_detail(server_space_name: &str) -> Option<ServerSpace> {
         let cfg = get_config();
         let server_space_list =  &cfg.server_space_list;
-        let server_space_opt = server_space_list.get(server_space_name);
-        match server_space_opt {
-            Some(server_space) => {
-                Some(ServerSpace {
-                    name: server_space.name.clone(),
-                    host: server_space.host.clone(),
-                    path: server_space.path.clone(),
-                    user: server_space.user.clone(),
-                    pass: server_space.pass.clone()
-                })
-            },
-            None => None
-        }
+        server_space_list.get(server_space_name).cloned()
     }
 
     pub fn remove_server_space(server_space_name: &str) -> Result<(), &str> {
@@ -109,6 +276,13 @@ impl Config {
         }
     }
 
+    /// 空间连接服务器使用的代理：优先使用空间的代理，其次是全局代理
+    pub fn proxy_for(server_space: &ServerSpace) -> Option<String> {
+        server_space.proxy.clone()
+            .or_else(|| get_config().proxy)
+            .filter(|proxy| !proxy.trim().is_empty())
+    }
+
     pub fn check_server_space_name_available(server_space_name: &str) -> bool {
         let cfg = get_config();
         let server_space_list = cfg.server_space_list;
@@ -127,7 +301,7 @@ fn test_add_server_space() {
 
 #[test]
 fn test_list_server_space() {
-    let list = Config::list_server_space();
+    let list = Config::server_spaces();
     println!("{:?}", list);
 }
 
@@ -0,0 +1,78 @@
+//! # 密码来源
+//! 配置文件中的密码可以是加密保存的密码，也可以是外部来源，连接时才读取：
+//! - `env:变量名`：读取环境变量，例如 CI 中注入的 `env:PROD_SSH_PASS`
+//! - `cmd:命令`：执行命令，使用命令输出的第一行，例如 `cmd:pass show servers/prod`
+
+use std::env;
+use std::process::{Command, Stdio};
+
+use crate::aes::{decrypt, encrypt};
+
+const ENV_PREFIX: &str = "env:";
+const CMD_PREFIX: &str = "cmd:";
+
+/// 是否是外部来源
+pub fn is_source(pass: &str) -> bool {
+    pass.starts_with(ENV_PREFIX) || pass.starts_with(CMD_PREFIX)
+}
+
+/// 把输入的密码转换为保存到配置文件中的值：外部来源原样保存，其他的加密保存
+pub fn store(input: &str) -> String {
+    let input = input.trim_end_matches(['\r', '\n']);
+    if is_source(input.trim()) {
+        input.trim().to_string()
+    } else {
+        encrypt(input).unwrap()
+    }
+}
+
+/// 读取密码，空字符串表示没有密码
+pub fn resolve(pass: &str) -> Result<String, String> {
+    if let Some(name) = pass.strip_prefix(ENV_PREFIX) {
+        let name = name.trim();
+        return env::var(name).map_err(|_| format!("环境变量 {} 不存在", name));
+    }
+    if let Some(command) = pass.strip_prefix(CMD_PREFIX) {
+        return run_command(command.trim());
+    }
+    if pass.is_empty() {
+        return Ok(String::new());
+    }
+    decrypt(pass).map_err(|err| format!("{:?}", err))
+}
+
+/// 执行密码命令，标准错误直接输出，方便命令提示解锁等信息
+fn run_command(command: &str) -> Result<String, String> {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    let output = shell.arg(command)
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|err| format!("执行密码命令失败：{}", err))?;
+    if !output.status.success() {
+        return Err(format!("密码命令执行失败：{}", output.status));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout.lines().next().unwrap_or_default().to_string())
+}
+
+#[test]
+fn test_resolve() {
+    env::set_var("RPUSH_TEST_CREDENTIAL", "s3cret");
+    assert_eq!(resolve("env:RPUSH_TEST_CREDENTIAL"), Ok("s3cret".to_string()));
+    assert!(resolve("env:RPUSH_TEST_CREDENTIAL_MISSING").is_err());
+    assert_eq!(resolve(""), Ok(String::new()));
+    if cfg!(unix) {
+        assert_eq!(resolve("cmd:printf 'hunter2\\nmetadata\\n'"), Ok("hunter2".to_string()));
+        assert!(resolve("cmd:exit 1").is_err());
+    }
+    assert_eq!(store(" env:CI_PASS\n"), "env:CI_PASS");
+}
@@ -0,0 +1,170 @@
+//! # git 信息
+//! 读取本地来源所在 git 仓库的提交、分支和是否有未提交的修改，用于操作历史和服务器上的版本标记。
+//!
+//! `push --git` 只推送 git 跟踪的文件：推送 HEAD 时使用工作区中被跟踪的文件，有未提交的修改时拒绝推送；
+//! 推送其他版本时把这个版本的文件导出到临时目录中再推送。
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use serde::{Deserialize, Serialize};
+
+use crate::temp::TempDir;
+
+/// git 仓库的状态
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Info {
+    /// 当前提交
+    pub commit: String,
+    /// 当前分支，分离头指针时为 None
+    pub branch: Option<String>,
+    /// 是否有未提交的修改（包括未跟踪的文件）
+    pub dirty: bool,
+}
+
+/// 路径所在 git 仓库的状态，不在仓库中或者没有安装 git 时返回 None
+pub fn info(path: &Path) -> Option<Info> {
+    let dir = source_dir(path).ok()?;
+    let commit = git(&dir, &["rev-parse", "HEAD"])?;
+    let branch = git(&dir, &["symbolic-ref", "--short", "-q", "HEAD"]);
+    let dirty = git(&dir, &["status", "--porcelain"]).is_some();
+    Some(Info { commit, branch, dirty })
+}
+
+/// `--git` 模式下要推送的内容
+pub struct Snapshot {
+    /// 推送的提交
+    pub info: Info,
+    /// 实际打包的来源，推送其他版本时是导出到临时目录中的来源
+    pub sources: Vec<PathBuf>,
+    /// 推送 HEAD 时只打包这些被跟踪的文件，为 None 时打包来源中的所有文件
+    pub files: Option<BTreeSet<PathBuf>>,
+    // 导出的临时目录，被丢弃时删除
+    export_dir: Option<TempDir>,
+}
+
+impl Snapshot {
+    /// 读取来源在 `rev` 版本中被跟踪的文件，所有来源必须在同一个 git 仓库中
+    ///
+    /// `rev` 就是当前的 HEAD 时，工作区中被跟踪的文件有未提交的修改会返回错误，除非 `allow_dirty`；
+    /// 其他版本导出到私有的临时目录中，和工作区无关。
+    pub fn new(sources: &[PathBuf], rev: &str, allow_dirty: bool) -> Result<Self, String> {
+        let dirs = sources.iter().map(|source| source_dir(source)).collect::<Result<Vec<_>, _>>()?;
+        let top_level = |dir: &Path| git(dir, &["rev-parse", "--show-toplevel"])
+            .ok_or_else(|| format!("不在 git 仓库中：{}", dir.display()));
+        let repo = top_level(&dirs[0])?;
+        for dir in &dirs[1..] {
+            if top_level(dir)? != repo {
+                return Err("所有来源必须在同一个 git 仓库中".to_string());
+            }
+        }
+        let repo = Path::new(&repo);
+
+        let commit = git(repo, &["rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", rev)])
+            .ok_or_else(|| format!("无效的 git 版本：{}", rev))?;
+        let head = git(repo, &["rev-parse", "--verify", "--quiet", "HEAD"]);
+        if head.as_deref() != Some(commit.as_str()) {
+            let export_dir = TempDir::new("git").map_err(|err| err.to_string())?;
+            let mut snapshot = Self {
+                info: Info { commit, branch: None, dirty: false },
+                sources: Vec::new(),
+                files: None,
+                export_dir: None,
+            };
+            snapshot.sources = snapshot.export(sources, &dirs, export_dir.path())?;
+            snapshot.export_dir = Some(export_dir);
+            return Ok(snapshot);
+        }
+
+        // 只检查要推送的路径，未跟踪的文件不会推送，不算未提交的修改
+        let mut args = vec!["status", "--porcelain", "--untracked-files=no", "--"];
+        let paths = sources.iter().map(|source| source.to_string_lossy()).collect::<Vec<_>>();
+        args.extend(paths.iter().map(|path| path.as_ref()));
+        let changes = git(repo, &args);
+        if let Some(changes) = &changes {
+            if !allow_dirty {
+                return Err(format!("有未提交的修改，提交后再推送，或者使用 --allow-dirty：\n{}", changes));
+            }
+        }
+
+        let mut files = BTreeSet::new();
+        for (source, dir) in sources.iter().zip(&dirs) {
+            let mut args = vec!["ls-tree", "-r", "-z", "--name-only", commit.as_str()];
+            let name = file_name(source, dir);
+            if let Some(name) = &name {
+                args.extend(["--", name.as_str()]);
+            }
+            let output = git_output(dir, &args)?;
+            files.extend(output.split('\0').filter(|path| !path.is_empty()).map(|path| dir.join(path)));
+        }
+        Ok(Self {
+            info: Info { commit, branch: git(repo, &["symbolic-ref", "--short", "-q", "HEAD"]), dirty: changes.is_some() },
+            sources: sources.to_vec(),
+            files: Some(files),
+            export_dir: None,
+        })
+    }
+
+    /// 把每个来源在这个版本中的文件导出到 `export_dir` 下的子目录中，返回导出后的来源
+    fn export(&self, sources: &[PathBuf], dirs: &[PathBuf], export_dir: &Path) -> Result<Vec<PathBuf>, String> {
+        let mut exported = Vec::new();
+        for (index, (source, dir)) in sources.iter().zip(dirs).enumerate() {
+            let target = export_dir.join(index.to_string());
+            fs::create_dir_all(&target).map_err(|err| err.to_string())?;
+            let name = file_name(source, dir);
+            let mut command = Command::new("git");
+            command.arg("-C").arg(dir).args(["archive", "--format=tar", &self.info.commit]);
+            if let Some(name) = &name {
+                command.args(["--", name]);
+            }
+            let mut child = command.stdout(Stdio::piped()).spawn().map_err(|err| err.to_string())?;
+            let unpacked = tar::Archive::new(child.stdout.take().unwrap()).unpack(&target);
+            let status = child.wait().map_err(|err| err.to_string())?;
+            if !status.success() {
+                return Err(format!("导出 {} 失败：{}", source.display(), status));
+            }
+            unpacked.map_err(|err| err.to_string())?;
+            exported.push(match name {
+                Some(name) => target.join(name),
+                None => target,
+            });
+        }
+        Ok(exported)
+    }
+}
+
+/// 来源是目录时就是它本身，是文件时是它所在的目录
+fn source_dir(source: &Path) -> Result<PathBuf, String> {
+    if source.is_dir() {
+        return Ok(source.to_path_buf());
+    }
+    source.parent().map(Path::to_path_buf).ok_or_else(|| format!("无效的来源：{}", source.display()))
+}
+
+/// 来源是文件时的文件名
+fn file_name(source: &Path, dir: &Path) -> Option<String> {
+    if source == dir {
+        return None;
+    }
+    source.file_name().map(|name| name.to_string_lossy().to_string())
+}
+
+/// 执行 git 命令，失败时返回 git 的错误信息
+fn git_output(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git").arg("-C").arg(dir).args(args).output().map_err(|err| format!("执行 git 失败：{}", err))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// 执行 git 命令，返回去掉首尾空白的输出，失败或输出为空时返回 None
+fn git(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git").arg("-C").arg(dir).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string()).filter(|output| !output.is_empty())
+}
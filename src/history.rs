@@ -0,0 +1,171 @@
+//! # 操作历史
+//! 每次 push、rmrf、restore 都会在当前用户目录下的 `.rpush_history` 中追加一行 JSON 记录，
+//! 记录时间、操作人、空间、来源、git 提交、压缩包 sha256、大小、耗时和结果，`rpush history` 可以查询。
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::ServerSpace;
+use crate::git;
+use crate::utils;
+
+// 历史文件名
+const HISTORY_FILE_NAME: &str = ".rpush_history";
+
+/// 操作类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    Push,
+    Rmrf,
+    Restore,
+}
+
+/// 一条历史记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    /// 开始时间，unix 时间戳（秒）
+    pub time: u64,
+    pub action: Action,
+    pub space: String,
+    /// 本机用户
+    pub user: String,
+    /// 服务器，`user@host:port`
+    pub server: String,
+    /// 服务器目录
+    pub remote_dir: String,
+    /// 推送的本地文件或目录
+    #[serde(default)]
+    pub sources: Vec<String>,
+    /// 来源所在 git 仓库的提交
+    #[serde(default)]
+    pub git_commit: Option<String>,
+    /// 推送的压缩包的 sha256
+    #[serde(default)]
+    pub hash: Option<String>,
+    /// 恢复的备份 id
+    #[serde(default)]
+    pub backup_id: Option<String>,
+    /// 推送的文件总大小（字节）
+    #[serde(default)]
+    pub bytes: u64,
+    /// 耗时（毫秒）
+    pub duration_ms: u64,
+    pub success: bool,
+    /// 失败原因
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// 正在进行的操作，结束时调用 `finish` 写入历史；没有调用就结束（例如中途出错返回）时按失败记录
+pub struct Record {
+    pub entry: Entry,
+    start: Instant,
+    finished: bool,
+}
+
+impl Record {
+    pub fn start(action: Action, server_space: &ServerSpace, remote_dir: &str) -> Self {
+        let entry = Entry {
+            time: utils::now(),
+            action,
+            space: server_space.name.clone(),
+            user: utils::local_user(),
+            server: format!("{}@{}:{}", server_space.user, server_space.host, server_space.port),
+            remote_dir: remote_dir.to_string(),
+            sources: Vec::new(),
+            git_commit: None,
+            hash: None,
+            backup_id: None,
+            bytes: 0,
+            duration_ms: 0,
+            success: false,
+            error: None,
+        };
+        Self { entry, start: Instant::now(), finished: false }
+    }
+
+    /// 记录推送的来源和来源所在 git 仓库的提交
+    pub fn sources(&mut self, sources: &[PathBuf], git: Option<&git::Info>) {
+        self.entry.sources = sources.iter().map(|source| source.display().to_string()).collect();
+        self.entry.git_commit = git.map(|git| git.commit.clone());
+    }
+
+    /// 操作结束，写入历史
+    pub fn finish(mut self, result: Result<(), String>) {
+        self.entry.success = result.is_ok();
+        self.entry.error = result.err();
+        self.write();
+    }
+
+    /// 操作被用户取消，不写入历史
+    pub fn cancel(mut self) {
+        self.finished = true;
+    }
+
+    fn write(&mut self) {
+        self.finished = true;
+        self.entry.duration_ms = self.start.elapsed().as_millis() as u64;
+        if let Err(err) = append(&self.entry) {
+            eprintln!("😔写入操作历史时发生错误：{}", err);
+        }
+    }
+}
+
+impl Drop for Record {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.write();
+        }
+    }
+}
+
+fn history_path() -> PathBuf {
+    dirs::home_dir().unwrap().join(HISTORY_FILE_NAME)
+}
+
+/// 追加一条记录
+fn append(entry: &Entry) -> io::Result<()> {
+    let line = serde_json::to_string(entry)?;
+    let mut file = OpenOptions::new().create(true).append(true).open(history_path())?;
+    writeln!(file, "{}", line)
+}
+
+/// 读取历史记录，按时间先后排列，无法解析的行会被跳过
+pub fn load() -> io::Result<Vec<Entry>> {
+    match fs::read_to_string(history_path()) {
+        Ok(content) => Ok(parse(&content)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(err),
+    }
+}
+
+/// 每行一条 JSON 记录
+fn parse(content: &str) -> Vec<Entry> {
+    content.lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+#[test]
+fn test_parse_history() {
+    let mut record = Record::start(Action::Push, &ServerSpace::new("web", "10.0.0.2", "/var/www", "root", ""), "/var/www");
+    record.entry.sources = vec!["/home/me/site \"v2\"".to_string()];
+    record.entry.bytes = 1024;
+    record.entry.error = Some("上传失败：连接断开".to_string());
+    record.finished = true;
+    let line = serde_json::to_string(&record.entry).unwrap();
+
+    let entries = parse(&format!("{}\nnot json\n\n{}\n", line, line));
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].action, Action::Push);
+    assert_eq!(entries[0].server, "root@10.0.0.2:22");
+    assert_eq!(entries[0].sources, vec!["/home/me/site \"v2\""]);
+    assert_eq!(entries[0].bytes, 1024);
+    assert_eq!(entries[0].error.as_deref(), Some("上传失败：连接断开"));
+}
@@ -0,0 +1,193 @@
+//! # 文件清单
+//! 计算本地目录和服务器空间中每个文件的权限和 sha256，用来比较两边的差异。
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+
+use sha2::{Digest, Sha256};
+
+use crate::archive::Entry;
+use crate::remote::quote;
+
+/// 清单中的一个文件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileEntry {
+    /// 权限位，例如 0o644，无法获取时为 None
+    pub mode: Option<u32>,
+    /// 文件内容的 sha256（十六进制）
+    pub hash: String,
+}
+
+/// 文件清单，key 为以 `/` 分隔的相对路径
+pub type Manifest = BTreeMap<String, FileEntry>;
+
+/// 文件差异
+#[derive(Debug, PartialEq, Eq)]
+pub enum Change {
+    /// 本地新增
+    Added,
+    /// 内容被修改
+    Modified,
+    /// 本地已删除，远程仍然存在
+    Deleted,
+    /// 内容相同，权限不同（远程权限，本地权限）
+    ModeChanged(u32, u32),
+}
+
+/// 计算要打包的本地文件的清单，权限优先使用打包时设置的权限
+pub fn local_manifest(entries: &[Entry]) -> io::Result<Manifest> {
+    let mut manifest = Manifest::new();
+    for entry in entries.iter().filter(|entry| !entry.is_dir) {
+        let mode = match entry.mode {
+            Some(mode) => Some(mode),
+            None => file_mode(&fs::metadata(&entry.local)?),
+        };
+        manifest.insert(entry.path.clone(), FileEntry {
+            mode,
+            hash: sha256_file(&entry.local)?,
+        });
+    }
+    Ok(manifest)
+}
+
+/// 计算文件的 sha256
+pub fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(to_hex(&hasher.finalize()))
+}
+
+/// 字节转十六进制字符串
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(unix)]
+fn file_mode(metadata: &fs::Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(metadata.permissions().mode() & 0o7777)
+}
+
+#[cfg(not(unix))]
+fn file_mode(_metadata: &fs::Metadata) -> Option<u32> {
+    None
+}
+
+/// 在服务器上列出目录中所有文件的权限和 sha256 的命令
+///
+/// 每行输出格式：`644 <sha256>  ./a/b.txt`
+pub fn remote_manifest_command(path: &str) -> String {
+    format!(
+        "cd {} && find . -type f -exec sh -c 'for f do printf \"%s \" \"$(stat -c %a \"$f\")\"; sha256sum \"$f\"; done' sh {{}} +",
+        quote(path)
+    )
+}
+
+/// 解析 [remote_manifest_command] 的输出
+pub fn parse_remote_manifest(output: &str) -> Manifest {
+    let mut manifest = Manifest::new();
+    for line in output.lines() {
+        let mut parts = line.splitn(2, ' ');
+        let (mode, rest) = match (parts.next(), parts.next()) {
+            (Some(mode), Some(rest)) => (mode, rest),
+            _ => continue,
+        };
+        let (hash, path) = match rest.split_once("  ") {
+            Some(val) => val,
+            None => continue,
+        };
+        let path = path.strip_prefix("./").unwrap_or(path);
+        manifest.insert(path.to_string(), FileEntry {
+            mode: u32::from_str_radix(mode, 8).ok(),
+            hash: hash.to_string(),
+        });
+    }
+    manifest
+}
+
+/// 比较本地清单和远程清单，按路径排序返回差异
+pub fn compare(local: &Manifest, remote: &Manifest) -> Vec<(String, Change)> {
+    let mut changes = Vec::new();
+    for (path, local_entry) in local {
+        match remote.get(path) {
+            None => changes.push((path.clone(), Change::Added)),
+            Some(remote_entry) if remote_entry.hash != local_entry.hash => {
+                changes.push((path.clone(), Change::Modified))
+            }
+            Some(remote_entry) => {
+                if let (Some(from), Some(to)) = (remote_entry.mode, local_entry.mode) {
+                    if from != to {
+                        changes.push((path.clone(), Change::ModeChanged(from, to)));
+                    }
+                }
+            }
+        }
+    }
+    for path in remote.keys() {
+        if !local.contains_key(path) {
+            changes.push((path.clone(), Change::Deleted));
+        }
+    }
+    changes.sort_by(|a, b| a.0.cmp(&b.0));
+    changes
+}
+
+/// 找出远程内容和本地不一致或者远程不存在的文件
+pub fn mismatches(local: &Manifest, remote: &Manifest) -> Vec<String> {
+    local.iter()
+        .filter(|(path, entry)| remote.get(*path).map(|remote_entry| &remote_entry.hash) != Some(&entry.hash))
+        .map(|(path, _)| path.clone())
+        .collect()
+}
+
+#[test]
+fn test_parse_remote_manifest() {
+    let output = "644 abc  ./index.html\n755 def  ./js/app  v2.js\n\nbroken line\n";
+    let manifest = parse_remote_manifest(output);
+    assert_eq!(manifest.len(), 2);
+    assert_eq!(manifest["index.html"], FileEntry { mode: Some(0o644), hash: "abc".to_string() });
+    assert_eq!(manifest["js/app  v2.js"].mode, Some(0o755));
+}
+
+#[test]
+fn test_compare() {
+    let entry = |mode, hash: &str| FileEntry { mode: Some(mode), hash: hash.to_string() };
+    let local = Manifest::from([
+        ("a".to_string(), entry(0o644, "1")),
+        ("b".to_string(), entry(0o644, "2")),
+        ("c".to_string(), entry(0o755, "3")),
+        ("d".to_string(), entry(0o644, "4")),
+    ]);
+    let remote = Manifest::from([
+        ("b".to_string(), entry(0o644, "x")),
+        ("c".to_string(), entry(0o644, "3")),
+        ("d".to_string(), entry(0o644, "4")),
+        ("e".to_string(), entry(0o644, "5")),
+    ]);
+    assert_eq!(compare(&local, &remote), vec![
+        ("a".to_string(), Change::Added),
+        ("b".to_string(), Change::Modified),
+        ("c".to_string(), Change::ModeChanged(0o644, 0o755)),
+        ("e".to_string(), Change::Deleted),
+    ]);
+    assert_eq!(mismatches(&local, &remote), vec!["a", "b"]);
+}
+
+#[test]
+fn test_local_manifest_mode() {
+    let dir = crate::temp::TempDir::new("test").unwrap();
+    fs::write(dir.path().join("a.txt"), b"a").unwrap();
+    let entry = |mode| Entry { path: "a.txt".to_string(), local: dir.path().join("a.txt"), is_dir: false, size: 1, mode };
+
+    let manifest = local_manifest(&[entry(Some(0o600))]).unwrap();
+    assert_eq!(manifest["a.txt"].mode, Some(0o600));
+    assert_eq!(manifest["a.txt"].hash, sha256_file(&dir.path().join("a.txt")).unwrap());
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(dir.path().join("a.txt"), fs::Permissions::from_mode(0o640)).unwrap();
+        assert_eq!(local_manifest(&[entry(None)]).unwrap()["a.txt"].mode, Some(0o640));
+    }
+}
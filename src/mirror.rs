@@ -0,0 +1,98 @@
+//! # 镜像推送
+//! `push --delete` 时找出服务器空间中本地已经不存在的文件，并生成删除命令。
+
+use std::collections::BTreeSet;
+
+use crate::archive::Entry;
+use crate::backup;
+use crate::remote::{quote, BATCH_SIZE};
+use crate::revision;
+use crate::utils as util;
+
+/// 列出服务器空间中所有文件（不包括目录）的命令
+pub fn remote_files_command(path: &str) -> String {
+    format!("cd {} && find . ! -type d", quote(path))
+}
+
+/// 解析 [remote_files_command] 的输出，返回相对路径
+pub fn parse_remote_files(output: &str) -> Vec<String> {
+    output.lines()
+        .filter_map(|line| line.strip_prefix("./"))
+        .map(String::from)
+        .collect()
+}
+
+/// 找出需要删除的远程文件
+///
+/// 本地存在、匹配排除规则、匹配保护规则的文件以及备份文件、版本标记都不会被删除
+pub fn deletions(remote_files: &[String], entries: &[Entry], excludes: &[String], protected: &[String]) -> Vec<String> {
+    let local = entries.iter()
+        .filter(|entry| !entry.is_dir)
+        .map(|entry| entry.path.as_str())
+        .collect::<BTreeSet<_>>();
+    let mut deleted = remote_files.iter()
+        .filter(|path| !local.contains(path.as_str()))
+        .filter(|path| !util::is_excluded(excludes, path) && !util::is_excluded(protected, path))
+        .filter(|path| !backup::is_backup_path(path) && path.as_str() != revision::FILE_NAME)
+        .cloned()
+        .collect::<Vec<_>>();
+    deleted.sort();
+    deleted
+}
+
+/// 生成删除文件的命令
+///
+/// 删除文件之后，会尝试删除因此变空并且本地不存在的目录
+pub fn delete_commands(path: &str, deleted: &[String], entries: &[Entry]) -> Vec<String> {
+    let local_dirs = entries.iter()
+        .filter(|entry| entry.is_dir)
+        .map(|entry| entry.path.as_str())
+        .collect::<BTreeSet<_>>();
+
+    // 被删除文件的上级目录，由深到浅排列
+    let mut dirs = deleted.iter()
+        .flat_map(|file| {
+            file.match_indices('/').map(move |(index, _)| &file[..index])
+        })
+        .filter(|dir| !local_dirs.contains(dir))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    dirs.sort_by_key(|dir| std::cmp::Reverse(dir.matches('/').count()));
+
+    let mut commands = deleted.chunks(BATCH_SIZE)
+        .map(|files| format!("cd {} && rm -f -- {}", quote(path), quote_all(files)))
+        .collect::<Vec<_>>();
+    commands.extend(dirs.chunks(BATCH_SIZE).map(|dirs| {
+        format!("cd {} && rmdir --ignore-fail-on-non-empty -- {}", quote(path), quote_all(dirs))
+    }));
+    commands
+}
+
+/// 用空格连接多个加上引号的路径
+pub fn quote_all<S: AsRef<str>>(paths: &[S]) -> String {
+    paths.iter().map(|path| quote(path.as_ref())).collect::<Vec<_>>().join(" ")
+}
+
+#[test]
+fn test_deletions() {
+    use std::path::PathBuf;
+
+    let entries = vec![
+        Entry { path: "index.html".to_string(), local: PathBuf::new(), is_dir: false, size: 0, mode: None },
+        Entry { path: "js".to_string(), local: PathBuf::new(), is_dir: true, size: 0, mode: None },
+        Entry { path: "js/app.js".to_string(), local: PathBuf::new(), is_dir: false, size: 0, mode: None },
+    ];
+    let remote = parse_remote_files("./index.html\n./js/app.js\n./js/old.js\n./old/a/b.css\n./uploads/a.png\n./app.log\n./.rpush-backups/1.tar.gz\n");
+    let excludes = vec!["*.log".to_string()];
+    let protected = vec!["uploads/".to_string()];
+
+    let deleted = deletions(&remote, &entries, &excludes, &protected);
+    assert_eq!(deleted, vec!["js/old.js", "old/a/b.css"]);
+
+    let commands = delete_commands("/var/www", &deleted, &entries);
+    assert_eq!(commands, vec![
+        "cd '/var/www' && rm -f -- 'js/old.js' 'old/a/b.css'",
+        "cd '/var/www' && rmdir --ignore-fail-on-non-empty -- 'old/a' 'old'",
+    ]);
+}
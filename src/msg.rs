@@ -4,23 +4,108 @@ pub const INPUT_SPACE_NAME_MSG: &str = "输入空间名称";
 pub const INPUT_HOST_ADDRESS: &str = "输入主机地址";
 pub const INPUT_TARGET_PATH: &str = "输入目标路径";
 pub const INPUT_USERNAME: &str = "输入主机用户名";
-pub const INPUT_PASSWORD: &str = "输入主机密码";
+pub const INPUT_PASSWORD: &str = "输入主机密码（留空不保存密码，每次连接时输入；也可以输入 env:环境变量 或 cmd:密码命令，输入 agent 使用 ssh-agent 认证）";
+pub const INPUT_PASSWORD_OPTIONAL: &str = "输入主机密码（留空使用私钥认证，输入 agent 使用 ssh-agent 认证）";
+pub const INPUT_SPACE_PASSWORD: &str = "输入空间的主机密码：";
+pub const INPUT_SPACE_NAME_OR_ALIAS: &str = "输入空间名称（留空使用 ssh 配置中的主机别名）";
+pub const INPUT_JUMP_HOST_ADDRESS: &str = "输入跳板机地址";
+pub const INPUT_JUMP_PORT: &str = "输入跳板机 ssh 端口（默认 22）";
+pub const INPUT_JUMP_USERNAME: &str = "输入跳板机用户名";
+pub const INPUT_JUMP_PASSWORD: &str = "输入跳板机密码（留空使用 ssh-agent 或私钥认证）";
+pub const INPUT_JUMP_HOST_PASSWORD: &str = "输入跳板机密码：";
 pub const ADD_SUCCESS: &str = "🎉添加成功";
+pub const IMPORT_SUCCESS: &str = "🎉已导入空间：";
+pub const IMPORT_SKIP_EXISTED: &str = "😄空间名称已存在，跳过：";
+pub const IMPORT_SKIP_NO_KEY: &str = "😄没有可用的私钥，跳过（可以用 rpush add --from-ssh-config 添加并输入密码）：";
+pub const ADD_JUMP_SUCCESS: &str = "🎉已添加跳板机，当前跳板机数量：";
 pub const REMOVE_SUCCESS: &str = "🎉删除成功";
 pub const UPLOAD_SUCCESS: &str = "🎉上传成功";
+pub const WATCH_START: &str = "👀开始监听，文件变化后自动推送，按 Ctrl+C 结束：";
+pub const CREATE_DIR_SUCCESS: &str = "🎉已创建服务器目录：";
 pub const SPACE_LIST_TITLE: &str = "空间列表：";
+pub const SPACE_LIST_HEADER: [&str; 6] = ["名称", "地址", "路径", "认证", "标签", "最后推送"];
 pub const RMRF_CONFIRM: &str = "确认要删除空间中的所有文件？(yes继续，任意输入退出)";
 pub const RMRF_SUCCESS: &str = "🎉空间中的文件已全部清除";
+pub const RMRF_ERR: &str = "😔清空空间时发生错误！";
+pub const MIRROR_DELETE_TITLE: &str = "以下文件在本地已不存在，将从空间中删除：";
+pub const MIRROR_DELETE_CONFIRM: &str = "确认删除以上文件并继续推送？(yes继续，任意输入退出)";
+pub const MIRROR_DELETE_SUCCESS: &str = "🎉已删除空间中本地不存在的文件，数量：";
+pub const BACKUP_SUCCESS: &str = "🎉已备份空间中的文件，备份 id：";
+pub const BACKUP_LIST_TITLE: &str = "备份列表：";
+pub const BACKUP_LIST_IS_EMPTY: &str = "😌空间中还没有备份";
+pub const RESTORE_CONFIRM: &str = "确认用备份覆盖空间中的所有文件？(yes继续，任意输入退出)";
+pub const RESTORE_SUCCESS: &str = "🎉已从备份恢复";
+pub const VERIFY_SUCCESS: &str = "🎉文件校验通过，校验文件数：";
+pub const SHOW_SECRET_CONFIRM: &str = "确认在终端中显示明文密码？(yes继续，任意输入退出)";
+pub const TEST_CONFIRM: &str = "是否现在测试空间配置？(y/n)";
+pub const TEST_TITLE: &str = "正在测试空间：";
+pub const TEST_SUCCESS: &str = "🎉空间配置正确，可以推送";
+pub const TEST_STEP_DNS: &str = "域名解析";
+pub const TEST_STEP_TCP: &str = "TCP 连接";
+pub const TEST_STEP_PROXY: &str = "代理连接";
+pub const TEST_STEP_HANDSHAKE: &str = "ssh 握手";
+pub const TEST_STEP_AUTH: &str = "身份认证";
+pub const TEST_STEP_PATH: &str = "空间路径";
+pub const TEST_STEP_SUDO: &str = "sudo";
+pub const TEST_STEP_TOOLS: &str = "服务器命令";
+pub const TEST_FREE_SPACE: &str = "可用空间 ";
+pub const HISTORY_IS_EMPTY: &str = "😌还没有操作历史";
+pub const REVISION_TITLE: &str = "当前部署的版本：";
+pub const REVISION_DIRTY: &str = "（有未提交的修改）";
+pub const REVISION_INCREMENTAL: &str = "😌这是 rpush watch 的增量同步，只推送了变化的文件，sha256 是增量压缩包的";
+pub const REVISION_IS_NOT_EXISTED: &str = "😌服务器目录中没有版本标记，还没有用 rpush 推送过";
+pub const DIFF_IS_EMPTY: &str = "🎉本地目录和空间中的文件完全一致";
+pub const DIFF_BINARY_FILE: &str = "二进制文件不同";
 
 pub const SPACE_NAME_IS_EMPTY: &str = "😔空间名称不能为空！";
 pub const SPACE_NAME_IS_EXISTED: &str = "😄空间名称已存在！";
 pub const HOST_ADDRESS_IS_EMPTY: &str = "😔主机地址不能为空！";
 pub const TARGET_PATH_IS_EMPTY: &str= "😔目标路径不能为空！";
 pub const USERNAME_IS_EMPTY: &str = "😔主机用户名不能为空！";
-pub const PASSWORD_IS_EMPTY: &str = "😔主机密码不能为空！";
 pub const SPACE_LIST_IS_EMPTY: &str = "😌空间列表为空";
 pub const SPACE_NAME_IS_NOT_EXISTED: &str = "😔空间不存在！";
+pub const INVALID_PORT: &str = "😔无效的端口！";
 pub const IS_NOT_DIR: &str = "😔无效的目录！";
+pub const PATH_IS_NOT_EXISTED: &str = "😔本地路径不存在：";
+pub const INVALID_DEST: &str = "😔无效的子目录，只能是空间路径下的相对路径：";
+pub const SHOW_SECRET_ERR: &str = "😔读取密码时发生错误！";
+pub const SSH_CONFIG_ERR: &str = "😔读取 ~/.ssh/config 时发生错误！";
+pub const SSH_CONFIG_IS_EMPTY: &str = "😌~/.ssh/config 中没有可以导入的主机";
 pub const UPLOAD_ERR: &str = "😔上传时发生错误，可能是空间信息配置不正确！";
-
-
+pub const GIT_ERR: &str = "😔git 模式无法推送：";
+pub const WATCH_ERR: &str = "😔监听本地目录时发生错误！";
+pub const HISTORY_ERR: &str = "😔读取操作历史时发生错误！";
+pub const REVISION_ERR: &str = "😔读取版本标记时发生错误！";
+pub const DIFF_ERR: &str = "😔获取空间文件清单时发生错误！";
+pub const READ_LOCAL_DIR_ERR: &str = "😔读取本地目录时发生错误！";
+#[cfg(unix)]
+pub const AGENT_NOT_RUNNING: &str = "😔没有找到 ssh-agent，请检查 SSH_AUTH_SOCK 环境变量";
+#[cfg(unix)]
+pub const AGENT_ERR: &str = "😔读取 ssh-agent 中的密钥时发生错误！";
+#[cfg(unix)]
+pub const AGENT_NO_IDENTITIES: &str = "😔ssh-agent 中没有密钥，请先用 ssh-add 添加";
+#[cfg(not(unix))]
+pub const AGENT_NOT_SUPPORTED: &str = "😔当前系统不支持 ssh-agent 认证";
+pub const CONNECT_ERR: &str = "😔连接服务器时发生错误，可能是空间信息配置不正确！";
+pub const CREATE_DIR_ERR: &str = "😔创建服务器目录时发生错误：";
+pub const PREFLIGHT_ERR: &str = "😔检查服务器目录时发生错误！";
+pub const SPACE_PATH_IS_NOT_EXISTED: &str = "😔空间路径不存在，加上 --create 可以自动创建：";
+pub const DIR_IS_NOT_WRITABLE: &str = "😔没有服务器目录的写入权限：";
+pub const NO_ENOUGH_SPACE: &str = "😔服务器磁盘空间不足，需要 / 可用：";
+pub const MIRROR_LIST_ERR: &str = "😔获取空间文件列表时发生错误！";
+pub const BACKUP_ERR: &str = "😔备份空间中的文件时发生错误，已取消推送！";
+pub const BACKUP_LIST_ERR: &str = "😔获取备份列表时发生错误！";
+pub const INVALID_BACKUP_ID: &str = "😔无效的备份 id，可以用 rpush backups 查看：";
+pub const RESTORE_ERR: &str = "😔恢复备份时发生错误，请确认备份 id 是否正确！";
+pub const TEST_FAILED: &str = "😔空间测试未通过，请根据上面的提示修改空间配置！";
+pub const PROXY_WITH_JUMP_HOSTS: &str = "不支持同时使用代理和跳板机";
+pub const JUMP_HOST_ERR: &str = "连接跳板机时发生错误：";
+pub const TEST_NO_ADDRESS: &str = "没有解析到地址";
+pub const TEST_PATH_IS_NOT_EXISTED: &str = "路径不存在，推送时加上 --create 可以自动创建";
+pub const TEST_PATH_IS_NOT_WRITABLE: &str = "没有写入权限";
+pub const TEST_MISSING_TOOLS: &str = "缺少命令：";
+pub const UPLOAD_RETRY: &str = "😔上传失败，正在重新连接并重试，第几次：";
+pub const ARCHIVE_ERR: &str = "😔打包压缩时发生错误！";
+pub const ARCHIVE_HASH_MISMATCH: &str = "服务器上压缩包的 sha256 和本地不一致，已拒绝解压";
+pub const VERIFY_MISMATCH: &str = "😔以下文件和本地不一致：";
+pub const COMPRESSION_NOT_SUPPORTED: &str = "😔服务器上缺少解压需要的命令，不支持这种压缩格式：";
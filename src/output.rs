@@ -0,0 +1,65 @@
+//! # 输出格式
+//! `list`、`detail` 等命令的输出格式：给人看的表格，或者给脚本使用的 JSON、YAML。
+
+use std::str::FromStr;
+
+use serde::Serialize;
+
+/// 输出格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Format {
+    #[default]
+    Table,
+    Json,
+    Yaml,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "table" => Ok(Format::Table),
+            "json" => Ok(Format::Json),
+            "yaml" => Ok(Format::Yaml),
+            _ => Err(format!("不支持的输出格式：{}", s)),
+        }
+    }
+}
+
+/// 按 JSON 或 YAML 格式输出，表格格式由各命令自己输出
+pub fn render<T: Serialize>(value: &T, format: Format) -> Result<String, String> {
+    match format {
+        Format::Json => serde_json::to_string(value).map_err(|err| err.to_string()),
+        Format::Yaml => serde_yaml::to_string(value)
+            .map(|yaml| yaml.trim_start_matches("---\n").trim_end().to_string())
+            .map_err(|err| err.to_string()),
+        Format::Table => Err("表格格式不能通用输出".to_string()),
+    }
+}
+
+#[test]
+fn test_render_json() {
+    #[derive(Serialize)]
+    struct Space {
+        name: String,
+        port: u16,
+        excludes: Vec<String>,
+        owner: Option<String>,
+        use_sudo: bool,
+    }
+
+    let space = Space {
+        name: "web \"prod\"\n".to_string(),
+        port: 22,
+        excludes: vec!["*.map".to_string()],
+        owner: None,
+        use_sudo: true,
+    };
+    assert_eq!(
+        render(&space, Format::Json).unwrap(),
+        r#"{"name":"web \"prod\"\n","port":22,"excludes":["*.map"],"owner":null,"use_sudo":true}"#
+    );
+    assert!(render(&space, Format::Yaml).unwrap().starts_with("name: "));
+    assert_eq!("JSON".parse::<Format>(), Ok(Format::Json));
+}
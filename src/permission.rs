@@ -0,0 +1,98 @@
+//! # 所有者和权限
+//! 解压后把推送的文件和目录改为空间配置的所有者、用户组和权限，例如 `www-data:www-data`、0644 和 0755。
+
+use crate::archive::Entry;
+use crate::config::ServerSpace;
+use crate::remote::{quote, BATCH_SIZE};
+
+/// 解压后要设置的所有者和权限
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Permissions {
+    pub owner: Option<String>,
+    pub group: Option<String>,
+    /// 文件权限，例如 0o644
+    pub file_mode: Option<u32>,
+    /// 目录权限，例如 0o755
+    pub dir_mode: Option<u32>,
+}
+
+impl Permissions {
+    /// 读取空间配置中的所有者和权限
+    pub fn from_space(server_space: &ServerSpace) -> Result<Self, String> {
+        let non_empty = |val: &Option<String>| val.as_ref().map(|val| val.trim().to_string()).filter(|val| !val.is_empty());
+        Ok(Self {
+            owner: non_empty(&server_space.owner),
+            group: non_empty(&server_space.group),
+            file_mode: non_empty(&server_space.file_mode).map(|mode| parse_mode(&mode)).transpose()?,
+            dir_mode: non_empty(&server_space.dir_mode).map(|mode| parse_mode(&mode)).transpose()?,
+        })
+    }
+
+    /// 在 `remote_dir` 中修改 `entries` 的所有者和权限的命令
+    pub fn commands(&self, remote_dir: &str, entries: &[Entry]) -> Vec<String> {
+        let mut commands = Vec::new();
+        let mut add = |command: String, paths: Vec<&str>| {
+            for batch in paths.chunks(BATCH_SIZE) {
+                let paths = batch.iter().map(|path| quote(path)).collect::<Vec<_>>().join(" ");
+                commands.push(format!("cd {} && {} -- {}", quote(remote_dir), command, paths));
+            }
+        };
+
+        let owner = match (&self.owner, &self.group) {
+            (Some(owner), Some(group)) => Some(format!("{}:{}", owner, group)),
+            (Some(owner), None) => Some(owner.clone()),
+            (None, Some(group)) => Some(format!(":{}", group)),
+            (None, None) => None,
+        };
+        if let Some(owner) = owner {
+            add(format!("chown {}", quote(&owner)), entries.iter().map(|entry| entry.path.as_str()).collect());
+        }
+        if let Some(mode) = self.file_mode {
+            add(format!("chmod {:04o}", mode), entries.iter().filter(|entry| !entry.is_dir).map(|entry| entry.path.as_str()).collect());
+        }
+        if let Some(mode) = self.dir_mode {
+            add(format!("chmod {:04o}", mode), entries.iter().filter(|entry| entry.is_dir).map(|entry| entry.path.as_str()).collect());
+        }
+        commands
+    }
+}
+
+/// 解析八进制权限，例如 `644`、`0644`、`0o755`
+pub fn parse_mode(mode: &str) -> Result<u32, String> {
+    let digits = mode.trim().trim_start_matches("0o");
+    u32::from_str_radix(digits, 8)
+        .ok()
+        .filter(|mode| *mode <= 0o7777)
+        .ok_or_else(|| format!("无效的权限：{}", mode))
+}
+
+#[test]
+fn test_parse_mode() {
+    assert_eq!(parse_mode("644"), Ok(0o644));
+    assert_eq!(parse_mode("0755"), Ok(0o755));
+    assert_eq!(parse_mode("0o2775"), Ok(0o2775));
+    assert!(parse_mode("0888").is_err());
+    assert!(parse_mode("17777").is_err());
+}
+
+#[test]
+fn test_commands() {
+    use std::path::PathBuf;
+
+    let entries = vec![
+        Entry { path: "js".to_string(), local: PathBuf::new(), is_dir: true, size: 0, mode: None },
+        Entry { path: "js/app.js".to_string(), local: PathBuf::new(), is_dir: false, size: 0, mode: None },
+    ];
+    let permissions = Permissions {
+        owner: Some("www-data".to_string()),
+        group: Some("www-data".to_string()),
+        file_mode: Some(0o644),
+        dir_mode: Some(0o755),
+    };
+    assert_eq!(permissions.commands("/var/www", &entries), vec![
+        "cd '/var/www' && chown 'www-data:www-data' -- 'js' 'js/app.js'",
+        "cd '/var/www' && chmod 0644 -- 'js/app.js'",
+        "cd '/var/www' && chmod 0755 -- 'js'",
+    ]);
+    assert!(Permissions::default().commands("/var/www", &entries).is_empty());
+}
@@ -0,0 +1,102 @@
+//! # 推送前检查
+//! 推送前检查服务器目录是否存在、是否可写以及剩余的磁盘空间，避免上传或解压到一半才失败。
+//! `rpush test` 也用这里的检查逐项诊断空间配置。
+
+use std::io::{self, BufRead, BufReader, Read};
+
+use crate::remote::quote;
+
+/// 服务器目录的检查结果
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Report {
+    /// 目录是否存在
+    pub exists: bool,
+    /// 是否有写入权限
+    pub writable: bool,
+    /// 目录所在磁盘的可用空间（字节），无法获取时为 None
+    pub free: Option<u64>,
+    /// 目录所在磁盘的挂载点，用来判断两个目录是否在同一个文件系统上
+    pub mount: Option<String>,
+}
+
+impl Report {
+    /// 可用空间是否足够存放 `required` 字节，无法获取可用空间时不做限制
+    pub fn has_space(&self, required: u64) -> bool {
+        self.free.is_none_or(|free| free >= required)
+    }
+}
+
+/// 检查服务器目录的命令，每行输出一项 `<名称>=<值>`
+pub fn check_command(path: &str) -> String {
+    format!(
+        "p={}; if [ -d \"$p\" ]; then echo exists=1; else echo exists=0; fi; \
+         if [ -w \"$p\" ]; then echo writable=1; else echo writable=0; fi; \
+         df -Pk \"$p\" 2>/dev/null | awk 'NR==2 {{print \"free=\" $4; print \"mount=\" $6}}'; true",
+        quote(path),
+    )
+}
+
+/// 解析 [check_command] 的输出
+pub fn parse_report(output: &str) -> Report {
+    let mut report = Report::default();
+    for (key, value) in output.lines().filter_map(|line| line.trim().split_once('=')) {
+        match key {
+            "exists" => report.exists = value == "1",
+            "writable" => report.writable = value == "1",
+            "free" => report.free = value.parse::<u64>().ok().map(|kib| kib * 1024),
+            "mount" => report.mount = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    report
+}
+
+/// 列出服务器上缺少的命令，每行输出一个
+pub fn missing_tools_command(tools: &[&str]) -> String {
+    format!(
+        "for t in {}; do command -v \"$t\" >/dev/null 2>&1 || echo \"$t\"; done; true",
+        tools.iter().map(|tool| quote(tool)).collect::<Vec<_>>().join(" "),
+    )
+}
+
+/// 解析 [missing_tools_command] 的输出
+pub fn parse_missing_tools(output: &str) -> Vec<String> {
+    output.lines().map(str::trim).filter(|line| !line.is_empty()).map(String::from).collect()
+}
+
+/// 读取 ssh 服务端的版本标识，例如 `SSH-2.0-OpenSSH_8.9`
+///
+/// 服务端在标识之前可能先发送其他文本行，这些行会被跳过
+pub fn read_server_banner(stream: impl Read) -> io::Result<String> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    for _ in 0..20 {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            break;
+        }
+        if line.starts_with("SSH-") {
+            return Ok(line.trim_end().to_string());
+        }
+    }
+    Err(io::Error::new(io::ErrorKind::InvalidData, "服务端没有返回 ssh 版本标识"))
+}
+
+#[test]
+fn test_parse_report() {
+    let report = parse_report("exists=1\nwritable=0\nfree=2048\nmount=/var\n");
+    assert_eq!(report, Report { exists: true, writable: false, free: Some(2 * 1024 * 1024), mount: Some("/var".to_string()) });
+    assert!(report.has_space(1024));
+    assert!(!report.has_space(3 * 1024 * 1024));
+
+    let report = parse_report("exists=0\nwritable=0\n");
+    assert!(!report.exists);
+    assert!(report.has_space(u64::MAX));
+}
+
+#[test]
+fn test_read_server_banner() {
+    let banner = read_server_banner("hello\r\nSSH-2.0-OpenSSH_8.9\r\n".as_bytes()).unwrap();
+    assert_eq!(banner, "SSH-2.0-OpenSSH_8.9");
+    assert!(read_server_banner("HTTP/1.1 400 Bad Request\r\n".as_bytes()).is_err());
+}
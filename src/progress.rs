@@ -0,0 +1,92 @@
+//! # 进度显示
+//! 推送时每个空间一个进度条，依次显示打包、上传和解压三个阶段。标准输出不是终端时不显示进度条。
+
+use std::io::{self, IsTerminal};
+use std::time::Duration;
+
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+
+/// 创建进度条容器，标准输出不是终端时隐藏
+pub fn multi() -> MultiProgress {
+    if io::stdout().is_terminal() {
+        MultiProgress::with_draw_target(ProgressDrawTarget::stdout())
+    } else {
+        MultiProgress::with_draw_target(ProgressDrawTarget::hidden())
+    }
+}
+
+/// 一个空间的推送进度
+pub struct PushProgress {
+    bar: ProgressBar,
+    total_files: usize,
+    files: usize,
+}
+
+impl PushProgress {
+    /// 在 `multi` 中添加一个进度条，`prefix` 一般是空间名称
+    pub fn new(multi: &MultiProgress, prefix: &str) -> Self {
+        let bar = multi.add(ProgressBar::new(0).with_prefix(prefix.to_string()));
+        Self { bar, total_files: 0, files: 0 }
+    }
+
+    /// 开始打包
+    pub fn archiving(&mut self, total_files: usize, total_bytes: u64) {
+        self.total_files = total_files;
+        self.files = 0;
+        self.start("{prefix} 打包 [{bar:30.green/white}] {bytes}/{total_bytes} {msg}", total_bytes);
+        self.bar.set_message(format!("0/{} 个文件", total_files));
+    }
+
+    /// 打包完一个文件或目录
+    pub fn archived(&mut self, bytes: u64) {
+        self.files += 1;
+        self.bar.inc(bytes);
+        self.bar.set_message(format!("{}/{} 个文件", self.files, self.total_files));
+    }
+
+    /// 开始上传
+    pub fn uploading(&self, total_bytes: u64) {
+        self.start("{prefix} 上传 [{bar:30.green/white}] {bytes}/{total_bytes} {bytes_per_sec} 剩余 {eta}", total_bytes);
+        self.bar.set_message("");
+    }
+
+    /// 已经上传的字节数
+    pub fn uploaded(&self, bytes: u64) {
+        self.bar.set_position(bytes);
+    }
+
+    /// 开始解压
+    pub fn extracting(&self) {
+        self.bar.set_style(ProgressStyle::with_template("{prefix} {spinner:.green} 解压中 {elapsed}").unwrap());
+        self.bar.enable_steady_tick(Duration::from_millis(100));
+    }
+
+    /// 推送完成
+    pub fn finish(&self) {
+        self.bar.set_style(ProgressStyle::with_template("{prefix} {msg}").unwrap());
+        self.bar.finish_with_message("完成");
+    }
+
+    /// 推送失败
+    pub fn abandon(&self) {
+        self.bar.set_style(ProgressStyle::with_template("{prefix} {msg}").unwrap());
+        self.bar.abandon_with_message("失败");
+    }
+
+    /// 清除进度条，由调用方输出结果
+    pub fn clear(&self) {
+        self.bar.finish_and_clear();
+    }
+
+    /// 暂停进度条输出信息
+    pub fn println(&self, msg: &str) {
+        self.bar.suspend(|| eprintln!("{}", msg));
+    }
+
+    fn start(&self, template: &str, len: u64) {
+        self.bar.disable_steady_tick();
+        self.bar.set_style(ProgressStyle::with_template(template).unwrap().progress_chars("=> "));
+        self.bar.set_length(len);
+        self.bar.reset();
+    }
+}
@@ -0,0 +1,145 @@
+//! # 远程命令
+//! 在服务器空间上执行 shell 命令的小工具
+//!
+//! 需要 sudo 的命令通过带伪终端的 shell 通道执行，sudo 提示输入密码时从终端写入密码。
+
+use std::error::Error;
+
+use ssh_rs::LocalSession;
+
+use crate::tunnel::Stream;
+
+/// ssh 会话
+pub type Session = LocalSession<Stream>;
+
+/// 每条命令最多包含的路径数量，避免命令过长
+pub const BATCH_SIZE: usize = 200;
+
+// 追加在命令输出末尾的退出码标记
+const EXIT_CODE_MARKER: &str = "__rpush_exit_code:";
+// sudo 命令输出开始的标记
+const START_MARKER: &str = "__rpush_start:";
+// sudo 提示输入密码时输出的标记
+const SUDO_PROMPT_MARKER: &str = "__rpush_sudo_prompt:";
+
+/// 用单引号包裹字符串，作为 shell 参数使用
+pub fn quote(val: &str) -> String {
+    format!("'{}'", val.replace('\'', r"'\''"))
+}
+
+/// 拼接远程路径
+pub fn join(base: &str, relative: &str) -> String {
+    format!("{}/{}", base.trim_end_matches('/'), relative.trim_start_matches('/'))
+}
+
+/// 执行远程命令，返回标准输出
+///
+/// 命令退出码不为 0 时返回错误
+pub fn run(session: &mut Session, command: &str) -> Result<String, Box<dyn Error>> {
+    let wrapped = format!("{{ {}\n}}; printf '\\n{}%s' \"$?\"", command, EXIT_CODE_MARKER);
+    let output = session.open_exec()?.send_command(&wrapped)?;
+    let output = String::from_utf8_lossy(&output);
+
+    let (stdout, code) = split_exit_code(&output).ok_or("远程命令没有返回退出码")?;
+    if code != 0 {
+        return Err(format!("远程命令执行失败（退出码 {}）：{}", code, command).into());
+    }
+    Ok(stdout.to_string())
+}
+
+/// 用 sudo 执行远程命令，返回标准输出
+///
+/// 命令在伪终端中执行，sudo 提示输入密码时写入 `password`，密码错误或命令退出码不为 0 时返回错误
+pub fn run_sudo(session: &mut Session, command: &str, password: &str) -> Result<String, Box<dyn Error>> {
+    let mut shell = session.open_shell()?;
+    shell.write(sudo_script(command).as_bytes())?;
+
+    let mut output = String::new();
+    let mut answered = 0;
+    let result = loop {
+        let data = shell.read()?;
+        if data.is_empty() {
+            break Err("sudo 命令没有返回退出码".into());
+        }
+        output.push_str(&String::from_utf8_lossy(&data));
+
+        // 每次提示输入密码只回答一次，再次提示说明密码错误
+        let prompts = output.matches(SUDO_PROMPT_MARKER).count();
+        if prompts > answered {
+            if answered > 0 {
+                break Err("sudo 密码不正确".into());
+            }
+            shell.write(format!("{}\n", password).as_bytes())?;
+            answered = prompts;
+        }
+
+        if let Some((stdout, code)) = parse_sudo_output(&output) {
+            break if code == 0 {
+                Ok(stdout)
+            } else {
+                Err(format!("sudo 命令执行失败（退出码 {}）：{} {}", code, command, stdout.trim()).into())
+            };
+        }
+    };
+    let _ = shell.close();
+    result
+}
+
+/// 在 shell 中执行的脚本
+///
+/// 标记用两段引号拼接，这样终端回显的脚本本身不会被当作标记
+fn sudo_script(command: &str) -> String {
+    let split = |marker: &str| format!("'{}''{}'", &marker[..7], &marker[7..]);
+    format!(
+        "stty -echo; printf '%s\\n' {start}; sudo -p {prompt} sh -c {command}; printf '\\n%s%s\\n' {exit} \"$?\"; exit\n",
+        start = split(START_MARKER),
+        prompt = split(SUDO_PROMPT_MARKER),
+        command = quote(command),
+        exit = split(EXIT_CODE_MARKER),
+    )
+}
+
+/// 从终端输出中取出 sudo 命令的标准输出和退出码，命令还没结束时返回 None
+fn parse_sudo_output(output: &str) -> Option<(String, i32)> {
+    let output = output.replace("\r\n", "\n");
+    let index = output.rfind(EXIT_CODE_MARKER)?;
+    let (code, _) = output[index + EXIT_CODE_MARKER.len()..].split_once('\n')?;
+    let code = code.trim().parse().ok()?;
+
+    let start = output.find(START_MARKER).map_or(0, |start| start + START_MARKER.len());
+    let stdout = output[start..index].replace(SUDO_PROMPT_MARKER, "");
+    Some((stdout.trim_matches('\n').to_string(), code))
+}
+
+/// 拆分命令输出和退出码
+fn split_exit_code(output: &str) -> Option<(&str, i32)> {
+    let index = output.rfind(EXIT_CODE_MARKER)?;
+    let code = output[index + EXIT_CODE_MARKER.len()..].trim().parse().ok()?;
+    let stdout = output[..index].strip_suffix('\n').unwrap_or(&output[..index]);
+    Some((stdout, code))
+}
+
+#[test]
+fn test_quote() {
+    assert_eq!(quote("/var/www"), "'/var/www'");
+    assert_eq!(quote("it's"), r"'it'\''s'");
+}
+
+#[test]
+fn test_split_exit_code() {
+    assert_eq!(split_exit_code("a\nb\n__rpush_exit_code:0"), Some(("a\nb", 0)));
+    assert_eq!(split_exit_code("\n__rpush_exit_code:2"), Some(("", 2)));
+    assert_eq!(split_exit_code("no marker"), None);
+}
+
+#[test]
+fn test_parse_sudo_output() {
+    let script = sudo_script("echo hi");
+    assert!(!script.contains(START_MARKER) && !script.contains(SUDO_PROMPT_MARKER) && !script.contains(EXIT_CODE_MARKER));
+
+    let output = "$ stty -echo\r\n__rpush_start:\r\n__rpush_sudo_prompt:\r\n20230301120000\r\n\r\n__rpush_exit_code:0";
+    assert_eq!(parse_sudo_output(output), None);
+    let output = format!("{}\r\n$ ", output);
+    assert_eq!(parse_sudo_output(&output), Some(("20230301120000".to_string(), 0)));
+    assert_eq!(parse_sudo_output("__rpush_start:\r\n\r\n__rpush_exit_code:1\r\n"), Some(("".to_string(), 1)));
+}
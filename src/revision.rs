@@ -0,0 +1,88 @@
+//! # 版本标记
+//! 推送完成后在服务器目录中写入 `.rpush-revision`（JSON），记录推送时间、推送人、来源、git 提交和压缩包 sha256，
+//! 服务器上的运维人员可以直接查看，`rpush status` 也会读取它。`rpush watch` 的同步会标记为增量同步。
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::git;
+use crate::remote::quote;
+use crate::utils;
+
+/// 版本标记文件名
+pub const FILE_NAME: &str = ".rpush-revision";
+
+/// 版本标记
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Revision {
+    /// 推送时间，unix 时间戳（秒）
+    pub time: u64,
+    /// 推送人，本机的用户名
+    pub user: String,
+    /// 推送的本机主机名
+    pub host: String,
+    /// 推送的本地文件或目录
+    pub sources: Vec<String>,
+    /// 来源所在 git 仓库的状态
+    #[serde(default)]
+    pub git: Option<git::Info>,
+    /// 压缩包的 sha256，增量同步时是这次同步的增量压缩包
+    pub hash: String,
+    /// 是否是 `rpush watch` 的增量同步，只推送了变化的文件
+    #[serde(default)]
+    pub incremental: bool,
+}
+
+impl Revision {
+    pub fn new(sources: &[PathBuf], git: Option<git::Info>, hash: &str) -> Self {
+        Self {
+            time: utils::now(),
+            user: utils::local_user(),
+            host: utils::local_host(),
+            sources: sources.iter().map(|source| source.display().to_string()).collect(),
+            git,
+            hash: hash.to_string(),
+            incremental: false,
+        }
+    }
+
+    /// 在服务器目录中写入版本标记的命令
+    pub fn write_command(&self, remote_dir: &str) -> Result<String, String> {
+        let json = serde_json::to_string(self).map_err(|err| err.to_string())?;
+        Ok(format!("cd {} && printf '%s\\n' {} > {}", quote(remote_dir), quote(&json), FILE_NAME))
+    }
+}
+
+/// 读取服务器目录中版本标记的命令，没有版本标记时输出为空
+pub fn read_command(remote_dir: &str) -> String {
+    format!("cd {} && cat {} 2>/dev/null || true", quote(remote_dir), FILE_NAME)
+}
+
+/// 解析 [read_command] 的输出，没有版本标记时返回 None
+pub fn parse(output: &str) -> Result<Option<Revision>, String> {
+    if output.trim().is_empty() {
+        return Ok(None);
+    }
+    serde_json::from_str(output.trim()).map(Some).map_err(|err| err.to_string())
+}
+
+#[test]
+fn test_parse_revision() {
+    let revision = Revision {
+        time: 1677659400,
+        user: "alice".to_string(),
+        host: "laptop".to_string(),
+        sources: vec!["/home/alice/site's dist".to_string()],
+        git: Some(git::Info { commit: "0070bc4d".to_string(), branch: Some("main".to_string()), dirty: true }),
+        hash: "e3b0c442".to_string(),
+        incremental: false,
+    };
+    let command = revision.write_command("/var/www").unwrap();
+    assert!(command.starts_with("cd '/var/www' && printf '%s\\n' '{\"time\":1677659400,"));
+    assert!(command.ends_with("' > .rpush-revision"));
+
+    let json = serde_json::to_string(&revision).unwrap();
+    assert_eq!(parse(&format!("{}\n", json)), Ok(Some(revision)));
+    assert_eq!(parse(""), Ok(None));
+}
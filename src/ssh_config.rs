@@ -0,0 +1,216 @@
+//! # ssh 配置导入
+//! 读取 `~/.ssh/config` 中的 Host 配置（HostName、Port、User、IdentityFile、ProxyJump），转换为空间配置。
+//!
+//! 和 ssh 一样，每一项取第一个匹配的 Host 块中的值，所以 `Host *` 中的默认值要写在最后。
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::config::{JumpHost, ServerSpace};
+use crate::utils::{glob_match, local_user};
+
+// ssh 默认尝试的私钥，只包含 ssh-rs 支持的类型
+const DEFAULT_IDENTITY_FILES: [&str; 2] = ["~/.ssh/id_ed25519", "~/.ssh/id_rsa"];
+
+/// 一个 Host 块
+#[derive(Debug, Default)]
+struct Block {
+    patterns: Vec<String>,
+    options: Vec<(String, String)>,
+}
+
+/// 解析后的 ssh 配置
+#[derive(Debug, Default)]
+pub struct SshConfig {
+    blocks: Vec<Block>,
+}
+
+/// 一台主机最终生效的配置
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SshHost {
+    pub alias: String,
+    pub host_name: Option<String>,
+    pub port: Option<u16>,
+    pub user: Option<String>,
+    pub identity_file: Option<String>,
+    pub proxy_jump: Option<String>,
+}
+
+impl SshConfig {
+    /// 读取 `~/.ssh/config`
+    pub fn load() -> io::Result<Self> {
+        let home_dir = dirs::home_dir().ok_or_else(|| io::Error::other("找不到用户目录"))?;
+        fs::read_to_string(home_dir.join(".ssh").join("config")).map(|content| Self::parse(&content))
+    }
+
+    /// 解析配置内容，不支持 `Match` 和 `Include`，`Match` 块会被忽略
+    pub fn parse(content: &str) -> Self {
+        // 第一个 Host 之前的配置对所有主机生效
+        let mut blocks = vec![Block { patterns: vec!["*".to_string()], ..Default::default() }];
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = match line.split_once(|c: char| c.is_whitespace() || c == '=') {
+                Some((key, value)) => (key.to_lowercase(), value.trim_start_matches(|c: char| c.is_whitespace() || c == '=').trim()),
+                None => continue,
+            };
+            let value = value.trim_matches('"');
+            match key.as_str() {
+                "host" => blocks.push(Block {
+                    patterns: value.split_whitespace().map(String::from).collect(),
+                    ..Default::default()
+                }),
+                "match" => blocks.push(Block::default()),
+                _ => blocks.last_mut().unwrap().options.push((key, value.to_string())),
+            }
+        }
+        Self { blocks }
+    }
+
+    /// 配置中明确写出的主机别名，不包含通配符规则
+    pub fn aliases(&self) -> Vec<String> {
+        let mut aliases = Vec::new();
+        for pattern in self.blocks.iter().flat_map(|block| &block.patterns) {
+            if !pattern.contains(['*', '?', '!']) && !aliases.contains(pattern) {
+                aliases.push(pattern.clone());
+            }
+        }
+        aliases
+    }
+
+    /// 主机别名最终生效的配置，别名没有出现在配置中时返回 None
+    pub fn host(&self, alias: &str) -> Option<SshHost> {
+        if !self.aliases().iter().any(|known| known == alias) {
+            return None;
+        }
+        let mut host = SshHost { alias: alias.to_string(), ..Default::default() };
+        for block in self.blocks.iter().filter(|block| block.matches(alias)) {
+            for (key, value) in &block.options {
+                let value = value.replace("%h", alias);
+                match key.as_str() {
+                    "hostname" if host.host_name.is_none() => host.host_name = Some(value),
+                    "port" if host.port.is_none() => host.port = value.parse().ok(),
+                    "user" if host.user.is_none() => host.user = Some(value),
+                    "identityfile" if host.identity_file.is_none() => host.identity_file = Some(value),
+                    "proxyjump" if host.proxy_jump.is_none() => host.proxy_jump = Some(value),
+                    _ => {}
+                }
+            }
+        }
+        Some(host)
+    }
+
+    /// 把主机别名转换为空间配置，空间名称使用别名，密码为空，使用私钥认证
+    pub fn server_space(&self, alias: &str, path: &str) -> Result<ServerSpace, String> {
+        let host = self.host(alias).ok_or_else(|| format!("~/.ssh/config 中没有主机：{}", alias))?;
+        let mut server_space = ServerSpace::new(
+            alias,
+            host.host_name.as_deref().unwrap_or(alias),
+            path,
+            &host.user.clone().unwrap_or_else(local_user),
+            "",
+        );
+        server_space.port = host.port.unwrap_or(22);
+        server_space.identity_file = host.identity_file.clone().or_else(default_identity_file);
+        if let Some(proxy_jump) = host.proxy_jump.as_deref().filter(|jump| !jump.eq_ignore_ascii_case("none")) {
+            server_space.jump_hosts = proxy_jump.split(',')
+                .map(|jump| self.jump_host(jump.trim()))
+                .collect::<Result<_, _>>()?;
+        }
+        Ok(server_space)
+    }
+
+    /// 解析 ProxyJump 中的一台跳板机 `[user@]host[:port]`，别名在配置中有 HostName 和 IdentityFile 时使用它们
+    fn jump_host(&self, jump: &str) -> Result<JumpHost, String> {
+        let (user, host_port) = match jump.rsplit_once('@') {
+            Some((user, host_port)) => (Some(user.to_string()), host_port),
+            None => (None, jump),
+        };
+        let (host, port) = match host_port.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port.parse::<u16>().map_err(|_| format!("无效的 ProxyJump：{}", jump))?)),
+            None => (host_port, None),
+        };
+        let configured = self.host(host).unwrap_or_default();
+        Ok(JumpHost {
+            host: configured.host_name.unwrap_or_else(|| host.to_string()),
+            port: port.or(configured.port).unwrap_or(22),
+            user: user.or(configured.user).unwrap_or_else(local_user),
+            pass: String::new(),
+            identity_file: configured.identity_file.or_else(default_identity_file),
+        })
+    }
+}
+
+impl Block {
+    /// 别名是否匹配 Host 规则，`!` 开头的规则匹配时整个块都不匹配
+    fn matches(&self, alias: &str) -> bool {
+        let mut matched = false;
+        for pattern in &self.patterns {
+            match pattern.strip_prefix('!') {
+                Some(pattern) if glob_match(pattern.as_bytes(), alias.as_bytes()) => return false,
+                Some(_) => {}
+                None => matched |= glob_match(pattern.as_bytes(), alias.as_bytes()),
+            }
+        }
+        matched
+    }
+}
+
+/// ssh 默认尝试的私钥中第一个存在的
+pub fn default_identity_file() -> Option<String> {
+    DEFAULT_IDENTITY_FILES.iter()
+        .find(|file| Path::new(&expand_home(file)).exists())
+        .map(|file| file.to_string())
+}
+
+/// 展开路径开始的 `~`
+pub fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home_dir)) => home_dir.join(rest).to_string_lossy().to_string(),
+        _ => path.to_string(),
+    }
+}
+
+#[test]
+fn test_parse_ssh_config() {
+    let ssh_config = SshConfig::parse("
+# 跳板机
+Host bastion
+    HostName bastion.example.com
+    User ops
+    Port 2200
+
+Host web web-*
+    HostName=10.0.0.2
+    User deploy
+    IdentityFile ~/.ssh/deploy_ed25519
+    ProxyJump bastion
+
+Host * !web
+    User nobody
+    Port 2222
+");
+    assert_eq!(ssh_config.aliases(), vec!["bastion", "web"]);
+    assert_eq!(ssh_config.host("web").unwrap(), SshHost {
+        alias: "web".to_string(),
+        host_name: Some("10.0.0.2".to_string()),
+        port: None,
+        user: Some("deploy".to_string()),
+        identity_file: Some("~/.ssh/deploy_ed25519".to_string()),
+        proxy_jump: Some("bastion".to_string()),
+    });
+    assert!(ssh_config.host("db").is_none());
+
+    let server_space = ssh_config.server_space("web", "/var/www").unwrap();
+    assert_eq!(server_space.host, "10.0.0.2");
+    assert_eq!(server_space.port, 22);
+    assert_eq!(server_space.user, "deploy");
+    assert_eq!(server_space.identity_file.as_deref(), Some("~/.ssh/deploy_ed25519"));
+    assert_eq!(server_space.jump_hosts.len(), 1);
+    assert_eq!(server_space.jump_hosts[0].host, "bastion.example.com");
+    assert_eq!(server_space.jump_hosts[0].port, 2200);
+    assert_eq!(server_space.jump_hosts[0].user, "ops");
+}
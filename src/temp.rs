@@ -0,0 +1,89 @@
+//! # 临时目录
+//! 本地的临时文件都放在私有的临时目录中：目录名随机，只有当前用户可以访问（Unix 上权限为 0700），
+//! 并且只在目录不存在时创建，不会使用别人预先放好的目录或符号链接，同时运行的多个 rpush 也不会互相覆盖。
+//! 目录被丢弃时连同其中的文件一起删除。
+
+use std::collections::hash_map::RandomState;
+use std::env;
+use std::fs;
+use std::hash::{BuildHasher, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// 目录名已经存在时重试的次数
+const ATTEMPTS: usize = 16;
+
+/// 私有的临时目录，被丢弃时删除
+#[derive(Debug)]
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// 在系统临时目录中创建 `rpush-<prefix>-<随机字符>` 目录
+    pub fn new(prefix: &str) -> io::Result<Self> {
+        for _ in 0..ATTEMPTS {
+            let path = env::temp_dir().join(format!("rpush-{}-{:016x}", prefix, random()));
+            match create(&path) {
+                Ok(_) => return Ok(Self { path }),
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(err) => return Err(err),
+            }
+        }
+        Err(io::Error::new(io::ErrorKind::AlreadyExists, "无法创建临时目录"))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// 随机数，`RandomState` 每次创建时使用新的随机密钥
+fn random() -> u64 {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u32(process::id());
+    hasher.write_usize(COUNTER.fetch_add(1, Ordering::SeqCst));
+    hasher.write_u128(SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_nanos()).unwrap_or_default());
+    hasher.finish()
+}
+
+#[cfg(unix)]
+fn create(path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::DirBuilderExt;
+
+    fs::DirBuilder::new().mode(0o700).create(path)
+}
+
+#[cfg(not(unix))]
+fn create(path: &Path) -> io::Result<()> {
+    fs::create_dir(path)
+}
+
+#[test]
+fn test_temp_dir() {
+    let first = TempDir::new("test").unwrap();
+    let second = TempDir::new("test").unwrap();
+    assert_ne!(first.path(), second.path());
+    assert!(first.path().is_dir());
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(fs::metadata(first.path()).unwrap().permissions().mode() & 0o777, 0o700);
+    }
+
+    let path = first.path().to_path_buf();
+    fs::write(path.join("a.txt"), b"a").unwrap();
+    drop(first);
+    assert!(!path.exists());
+}
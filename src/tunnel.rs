@@ -0,0 +1,146 @@
+//! # 连接通道
+//! 建立到服务器的底层连接：直接 TCP 连接、经过代理连接，或者经过跳板机连接。
+//!
+//! 经过跳板机时和 `ssh -J` 相同：先和第一台跳板机建立 ssh 会话，在它的 direct-tcpip 通道上连接下一台，
+//! 最后一台跳板机的通道连接到服务器，ssh 会话在这个转发的连接上建立。跳板机的认证也由 ssh-rs 完成，
+//! 不需要本机的 OpenSSH。
+
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+
+use ssh_rs::{ssh, LocalDirectTcpip};
+
+use crate::config::{Config, JumpHost, ServerSpace};
+use crate::msg::{JUMP_HOST_ERR, PROXY_WITH_JUMP_HOSTS};
+use crate::proxy::{self, Proxy};
+use crate::remote::Session;
+use crate::ssh_config;
+
+/// 到服务器的底层连接
+pub enum Stream {
+    /// 直接或经过代理的 TCP 连接
+    Tcp(TcpStream),
+    /// 经过跳板机的 direct-tcpip 通道转发的连接
+    Jump(Box<LocalDirectTcpip<Stream>>),
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Stream::Tcp(stream) => stream.read(buf),
+            Stream::Jump(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Stream::Tcp(stream) => stream.write(buf),
+            Stream::Jump(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Stream::Tcp(stream) => stream.flush(),
+            Stream::Jump(stream) => stream.flush(),
+        }
+    }
+}
+
+/// 建立到空间服务器的连接
+///
+/// 和 ssh-rs 自己建立的连接一样，连接是非阻塞的：没有数据可读时返回 `WouldBlock`
+pub fn connect(server_space: &ServerSpace) -> io::Result<Stream> {
+    let proxy = proxy_of(server_space)?;
+    if server_space.jump_hosts.is_empty() {
+        let stream = match proxy {
+            Some(proxy) => proxy::connect(&proxy, &server_space.host, server_space.port)?,
+            None => TcpStream::connect((server_space.host.as_str(), server_space.port))?,
+        };
+        stream.set_nonblocking(true)?;
+        return Ok(Stream::Tcp(stream));
+    }
+    if proxy.is_some() {
+        return Err(io::Error::other(PROXY_WITH_JUMP_HOSTS));
+    }
+
+    let first = &server_space.jump_hosts[0];
+    let stream = TcpStream::connect((first.host.as_str(), first.port))?;
+    stream.set_nonblocking(true)?;
+    let mut stream = Stream::Tcp(stream);
+    // 每台跳板机转发到下一台，最后一台转发到服务器
+    for (jump, (host, port)) in server_space.jump_hosts.iter().zip(forward_targets(server_space)) {
+        let jump_err = |err: String| io::Error::other(format!("{}{}@{}:{} {}", JUMP_HOST_ERR, jump.user, jump.host, jump.port, err));
+        let mut session = jump_session(jump, stream).map_err(jump_err)?;
+        let channel = session.open_direct_tcpip(host, port).map_err(|err| jump_err(err.to_string()))?;
+        stream = Stream::Jump(Box::new(channel));
+    }
+    Ok(stream)
+}
+
+/// 空间使用的代理，没有配置时返回 `None`
+pub fn proxy_of(server_space: &ServerSpace) -> io::Result<Option<Proxy>> {
+    Config::proxy_for(server_space)
+        .map(|proxy| proxy.parse::<Proxy>().map_err(io::Error::other))
+        .transpose()
+}
+
+/// 每台跳板机要转发到的地址：下一台跳板机，最后一台转发到服务器
+fn forward_targets(server_space: &ServerSpace) -> Vec<(&str, u16)> {
+    server_space.jump_hosts.iter()
+        .skip(1)
+        .map(|jump| (jump.host.as_str(), jump.port))
+        .chain([(server_space.host.as_str(), server_space.port)])
+        .collect()
+}
+
+/// 和跳板机建立 ssh 会话
+///
+/// ssh-agent 中有密钥时先尝试这些密钥，然后是配置的私钥和密码；三者都没有时连接时输入密码
+fn jump_session(jump: &JumpHost, stream: Stream) -> Result<Session, String> {
+    let mut builder = ssh::create_session().username(&jump.user);
+    let agent = agent_has_identities();
+    if agent {
+        builder = crate::with_agent(builder)?;
+    }
+    if let Some(identity_file) = &jump.identity_file {
+        builder = builder.private_key_path(ssh_config::expand_home(identity_file));
+    }
+    let pass = if !jump.pass.is_empty() {
+        crate::resolve_once(&format!("{}@{}:{}", jump.user, jump.host, jump.port), &jump.pass)?
+    } else if jump.identity_file.is_none() && !agent {
+        crate::prompt_jump_password(jump)
+    } else {
+        String::new()
+    };
+    builder.password(&pass)
+        .connect_bio(stream)
+        .map(|connector| connector.run_local())
+        .map_err(|err| err.to_string())
+}
+
+/// ssh-agent 是否在运行并且有密钥
+#[cfg(unix)]
+fn agent_has_identities() -> bool {
+    crate::agent::socket()
+        .and_then(|socket| crate::agent::identities(&socket).ok())
+        .is_some_and(|identities| !identities.is_empty())
+}
+
+#[cfg(not(unix))]
+fn agent_has_identities() -> bool {
+    false
+}
+
+#[test]
+fn test_forward_targets() {
+    let mut server_space = ServerSpace::new("web", "10.0.0.2", "/var/www", "root", "");
+    server_space.port = 2222;
+    server_space.jump_hosts = vec![
+        JumpHost { host: "bastion.example.com".to_string(), port: 22, user: "ops".to_string(), pass: String::new(), identity_file: None },
+        JumpHost { host: "10.0.0.1".to_string(), port: 2200, user: "deploy".to_string(), pass: String::new(), identity_file: None },
+    ];
+    assert_eq!(forward_targets(&server_space), [("10.0.0.1", 2200), ("10.0.0.2", 2222)]);
+}
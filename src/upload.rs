@@ -0,0 +1,144 @@
+//! # 断点续传
+//! 把文件分块上传到服务器上的 `<文件名>.part`，每块上传完成后追加到 `.part` 文件中。
+//! 上传中断后再次上传时，先校验已上传部分的大小和 sha256，校验通过就从断开的位置继续上传。
+
+use std::error::Error;
+use std::ffi::OsStr;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::process;
+use std::time::Duration;
+
+use sha2::{Digest, Sha256};
+
+use crate::manifest::to_hex;
+use crate::remote::{self, join, quote, Session};
+use crate::temp::TempDir;
+
+/// 每块最小的大小
+pub const MIN_CHUNK_SIZE: u64 = 256 * 1024;
+/// 每块最大的大小
+pub const MAX_CHUNK_SIZE: u64 = 8 * 1024 * 1024;
+
+/// 大小为 `total` 的文件每块的大小，大约分成 100 块，使进度条平滑前进
+pub fn chunk_size(total: u64) -> u64 {
+    (total / 100).clamp(MIN_CHUNK_SIZE, MAX_CHUNK_SIZE)
+}
+
+/// 创建用户自己的上传目录并输出它的绝对路径的命令，压缩包先上传到这里再解压到空间路径
+pub fn user_dir_command() -> &'static str {
+    "mkdir -p \"$HOME/.rpush-uploads\" && cd \"$HOME/.rpush-uploads\" && pwd"
+}
+
+/// 第 `attempt` 次重试前等待的时间，从 2 秒开始翻倍，最多 60 秒
+pub fn backoff(attempt: u32) -> Duration {
+    Duration::from_secs(2u64.saturating_pow(attempt).min(60))
+}
+
+/// 上传本地文件到服务器目录 `remote_dir`，支持断点续传
+///
+/// 每上传完一块调用一次 `on_progress`，参数为已经上传的总字节数
+pub fn upload(session: &mut Session, local_path: &Path, remote_dir: &str, mut on_progress: impl FnMut(u64)) -> Result<(), Box<dyn Error>> {
+    let file_name = local_path.file_name().ok_or("无效的文件名")?.to_string_lossy().to_string();
+    let local_size = fs::metadata(local_path)?.len();
+    let remote_file = join(remote_dir, &file_name);
+    let partial_file = format!("{}.part", remote_file);
+
+    // 清理之前中断的上传留下的分块文件
+    remote::run(session, &format!("rm -f -- {}.*.chunk", quote(&remote_file)))?;
+
+    // 已经上传的部分
+    let mut offset = uploaded_size(session, local_path, local_size, &partial_file)?;
+    on_progress(offset);
+
+    // 逐块上传，每块先写到私有临时目录中的文件并上传，再追加到 .part 文件
+    let temp_dir = TempDir::new("chunk")?;
+    let chunk_name = format!("{}.{}.chunk", file_name, process::id());
+    let local_chunk = temp_dir.path().join(&chunk_name);
+    let remote_chunk = join(remote_dir, &chunk_name);
+    let chunk_size = chunk_size(local_size);
+    let mut file = File::open(local_path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    while offset < local_size {
+        let len = chunk_size.min(local_size - offset);
+        write_chunk(&mut file, &local_chunk, len)?;
+        let result = session.open_scp()?.upload(local_chunk.as_os_str(), OsStr::new(remote_dir));
+        fs::remove_file(&local_chunk)?;
+        result?;
+        remote::run(session, &format!(
+            "cat {chunk} >> {part} && rm -f {chunk}",
+            chunk = quote(&remote_chunk),
+            part = quote(&partial_file),
+        ))?;
+        offset += len;
+        on_progress(offset);
+    }
+
+    // 校验大小后改为正式的文件名
+    let size = remote::run(session, &format!("touch {part} && stat -c %s {part}", part = quote(&partial_file)))?;
+    if size.trim().parse::<u64>()? != local_size {
+        return Err(format!("上传后的文件大小不一致：{}", partial_file).into());
+    }
+    remote::run(session, &format!("mv -f {} {}", quote(&partial_file), quote(&remote_file)))?;
+    Ok(())
+}
+
+/// 获取服务器上已经上传并且校验通过的字节数，校验不通过时删除 `.part` 文件重新上传
+fn uploaded_size(session: &mut Session, local_path: &Path, local_size: u64, partial_file: &str) -> Result<u64, Box<dyn Error>> {
+    let output = remote::run(session, &format!(
+        "if [ -f {part} ]; then stat -c %s {part}; sha256sum {part}; else echo 0; fi",
+        part = quote(partial_file),
+    ))?;
+    let mut lines = output.lines();
+    let size = lines.next().unwrap_or("0").trim().parse::<u64>()?;
+    if size == 0 {
+        return Ok(0);
+    }
+    let remote_hash = lines.next().and_then(|line| line.split_whitespace().next()).unwrap_or_default();
+    if size <= local_size && sha256_prefix(local_path, size)? == remote_hash {
+        return Ok(size);
+    }
+    remote::run(session, &format!("rm -f {}", quote(partial_file)))?;
+    Ok(0)
+}
+
+/// 计算文件前 `len` 个字节的 sha256
+fn sha256_prefix(path: &Path, len: u64) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?.take(len), &mut hasher)?;
+    Ok(to_hex(&hasher.finalize()))
+}
+
+/// 从 `file` 当前位置读取 `len` 个字节写入 `chunk_path`
+fn write_chunk(file: &mut File, chunk_path: &Path, len: u64) -> io::Result<()> {
+    let mut chunk = File::create(chunk_path)?;
+    io::copy(&mut Read::by_ref(file).take(len), &mut chunk)?;
+    chunk.flush()
+}
+
+#[test]
+fn test_backoff() {
+    assert_eq!(backoff(1), Duration::from_secs(2));
+    assert_eq!(backoff(3), Duration::from_secs(8));
+    assert_eq!(backoff(10), Duration::from_secs(60));
+}
+
+#[test]
+fn test_chunk_size() {
+    assert_eq!(chunk_size(0), MIN_CHUNK_SIZE);
+    assert_eq!(chunk_size(10 * 1024 * 1024), MIN_CHUNK_SIZE);
+    assert_eq!(chunk_size(50 * 1024 * 1024), 512 * 1024);
+    assert_eq!(chunk_size(100 * 1024 * 1024), 1024 * 1024);
+    assert_eq!(chunk_size(10 * 1024 * 1024 * 1024), MAX_CHUNK_SIZE);
+}
+
+#[test]
+fn test_sha256_prefix() {
+    let temp_dir = TempDir::new("test").unwrap();
+    let path = temp_dir.path().join("hello.txt");
+    fs::write(&path, b"hello world").unwrap();
+    let prefix = sha256_prefix(&path, 5).unwrap();
+    // sha256("hello")
+    assert_eq!(prefix, "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824");
+}
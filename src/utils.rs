@@ -1,18 +1,23 @@
 //! # 小工具👸🏻
 
+use std::env;
 use std::io::stdin;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 /// 判断字符串切片是否为空
 pub fn is_empty(val: &str) -> bool {
     val.trim().len() == 0
 }
 
-/// 去掉路径开始的路径分隔符
-pub fn del_start_separator(path: &str) -> &str {
-    if path.starts_with(std::path::MAIN_SEPARATOR) {
-        return &path[1..];
+/// 规范化空间路径下的子目录，去掉多余的 `/` 和 `.`，包含 `..` 时返回 None
+pub fn normalize_sub_path(path: &str) -> Option<String> {
+    let parts = path.split('/')
+        .filter(|part| !part.is_empty() && *part != ".")
+        .collect::<Vec<_>>();
+    if parts.contains(&"..") {
+        return None;
     }
-    path
+    Some(parts.join("/"))
 }
 
 /// 读取控制台输入
@@ -20,4 +25,135 @@ pub fn read_console() -> String {
     let mut v: String = String::new();
     stdin().read_line(&mut v).expect("read_line error!");
     String::from(v.trim())
-}
\ No newline at end of file
+}
+
+/// 判断相对路径是否匹配排除规则
+///
+/// 规则支持 `*`、`?` 和 `**` 通配符。
+/// 不含 `/` 的规则匹配路径中的任意一级，例如 `*.log`、`node_modules`；
+/// 含 `/` 的规则从根目录开始匹配，并且匹配到目录时其下所有文件都算匹配，例如 `uploads/`、`static/*.map`。
+pub fn is_excluded(patterns: &[String], path: &str) -> bool {
+    patterns.iter().any(|pattern| {
+        let anchored = pattern.contains('/');
+        let pattern = pattern.trim().trim_matches('/');
+        if pattern.is_empty() {
+            return false;
+        }
+        if anchored {
+            let mut prefix = String::new();
+            path.split('/').any(|part| {
+                if !prefix.is_empty() {
+                    prefix.push('/');
+                }
+                prefix.push_str(part);
+                glob_match(pattern.as_bytes(), prefix.as_bytes())
+            })
+        } else {
+            path.split('/').any(|part| glob_match(pattern.as_bytes(), part.as_bytes()))
+        }
+    })
+}
+
+/// 简单的通配符匹配，`*` 不跨越 `/`，`**` 可以跨越 `/`
+pub fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some(b'*') if pattern.get(1) == Some(&b'*') => {
+            let rest = pattern[2..].strip_prefix(b"/").unwrap_or(&pattern[2..]);
+            (0..=text.len()).any(|i| glob_match(rest, &text[i..]))
+        }
+        Some(b'*') => {
+            let rest = &pattern[1..];
+            (0..=text.len())
+                .take_while(|&i| i == 0 || text[i - 1] != b'/')
+                .any(|i| glob_match(rest, &text[i..]))
+        }
+        Some(b'?') => !text.is_empty() && text[0] != b'/' && glob_match(&pattern[1..], &text[1..]),
+        Some(c) => text.first() == Some(c) && glob_match(&pattern[1..], &text[1..]),
+    }
+}
+
+/// 本机的用户名
+pub fn local_user() -> String {
+    env::var("USER").or_else(|_| env::var("USERNAME")).unwrap_or_else(|_| "root".to_string())
+}
+
+/// 本机的主机名
+pub fn local_host() -> String {
+    env::var("HOSTNAME").or_else(|_| env::var("COMPUTERNAME")).ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|host| host.trim().to_string())
+        .filter(|host| !host.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// 当前的 unix 时间戳（秒）
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_secs()).unwrap_or_default()
+}
+
+/// 把 unix 时间戳格式化为 UTC 时间，例如 `2023-03-01 08:30 UTC`
+pub fn format_time(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let rest = secs % 86400;
+    // 按公历从 1970-01-01 起的天数计算年月日
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02} {:02}:{:02} UTC", year, month, day, rest / 3600, rest % 3600 / 60)
+}
+
+/// 字符串在终端中的显示宽度，中文等全角字符占两列
+pub fn display_width(val: &str) -> usize {
+    val.chars()
+        .map(|c| if matches!(c as u32, 0x1100..=0x115F | 0x2E80..=0xA4CF | 0xAC00..=0xD7A3 | 0xF900..=0xFAFF | 0xFE30..=0xFE4F | 0xFF00..=0xFF60 | 0xFFE0..=0xFFE6) { 2 } else { 1 })
+        .sum()
+}
+
+/// 把字符串补齐到指定的显示宽度
+pub fn pad(val: &str, width: usize) -> String {
+    format!("{}{}", val, " ".repeat(width.saturating_sub(display_width(val))))
+}
+
+#[test]
+fn test_is_excluded() {
+    let patterns = vec![
+        "*.log".to_string(),
+        "node_modules".to_string(),
+        "uploads/".to_string(),
+        "static/*.map".to_string(),
+        "docs/**/draft.md".to_string(),
+    ];
+    assert!(is_excluded(&patterns, "error.log"));
+    assert!(is_excluded(&patterns, "logs/app.log"));
+    assert!(is_excluded(&patterns, "web/node_modules/a/index.js"));
+    assert!(is_excluded(&patterns, "uploads"));
+    assert!(is_excluded(&patterns, "uploads/2023/a.png"));
+    assert!(is_excluded(&patterns, "static/app.js.map"));
+    assert!(is_excluded(&patterns, "docs/draft.md"));
+    assert!(is_excluded(&patterns, "docs/a/b/draft.md"));
+    assert!(!is_excluded(&patterns, "static/js/app.js.map"));
+    assert!(!is_excluded(&patterns, "web/uploads/a.png"));
+    assert!(!is_excluded(&patterns, "index.html"));
+}
+
+#[test]
+fn test_normalize_sub_path() {
+    assert_eq!(normalize_sub_path("/static//js/./"), Some("static/js".to_string()));
+    assert_eq!(normalize_sub_path(""), Some("".to_string()));
+    assert_eq!(normalize_sub_path("static/../.."), None);
+}
+
+#[test]
+fn test_format_time() {
+    assert_eq!(format_time(0), "1970-01-01 00:00 UTC");
+    assert_eq!(format_time(1677659400), "2023-03-01 08:30 UTC");
+    assert_eq!(format_time(1709164800), "2024-02-29 00:00 UTC");
+    assert_eq!(pad("名称", 6), "名称  ");
+}
@@ -0,0 +1,174 @@
+//! # 监听推送
+//! `rpush watch` 监听本地目录，文件变化停止一段时间（防抖）后，只把变化的文件打包推送到服务器，
+//! 整个监听期间使用同一个 ssh 连接。
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+use std::time::Duration;
+
+use notify_debouncer_mini::notify::{self, RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+
+use crate::archive::{self, Entry};
+use crate::mirror;
+use crate::remote::{quote, BATCH_SIZE};
+use crate::revision;
+use crate::utils as util;
+
+/// 每次同步的结果中最多列出的文件数
+pub const SUMMARY_NAMES: usize = 3;
+
+/// 一次同步要推送和删除的文件
+#[derive(Debug, Default)]
+pub struct Changes {
+    /// 新增或修改的文件和目录，目录排在它的内容之前
+    pub entries: Vec<Entry>,
+    /// 本地已经不存在的文件或目录，以 `/` 分隔的相对路径
+    pub deleted: Vec<String>,
+}
+
+impl Changes {
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty() && self.deleted.is_empty()
+    }
+}
+
+/// 递归监听目录，`debounce` 时间内没有新的变化才发送一批变化的路径
+///
+/// 返回的 `Debouncer` 需要一直持有，被丢弃时停止监听
+pub fn watch(dir: &Path, debounce: Duration) -> notify::Result<(Debouncer<RecommendedWatcher>, Receiver<DebounceEventResult>)> {
+    let (tx, rx) = channel();
+    let mut debouncer = new_debouncer(debounce, tx)?;
+    debouncer.watcher().watch(dir, RecursiveMode::Recursive)?;
+    Ok((debouncer, rx))
+}
+
+/// 根据变化的路径整理出要推送和删除的文件，跳过匹配排除规则的路径
+///
+/// 新出现的目录会推送其中的所有文件，例如从别处移动过来的目录只会有一个变化事件
+pub fn changes(dir: &Path, paths: &[PathBuf], excludes: &[String]) -> io::Result<Changes> {
+    let mut entries = BTreeMap::new();
+    let mut deleted = BTreeSet::new();
+    for path in paths {
+        let relative = match relative_path(dir, path) {
+            Some(relative) => relative,
+            None => continue,
+        };
+        if relative == revision::FILE_NAME || util::is_excluded(excludes, &relative) {
+            continue;
+        }
+        match fs::metadata(path) {
+            Ok(metadata) if metadata.is_dir() => {
+                for entry in archive::collect_sub(dir, &relative, excludes)? {
+                    entries.insert(entry.path.clone(), entry);
+                }
+                entries.insert(relative.clone(), Entry { path: relative, local: path.clone(), is_dir: true, size: 0, mode: None });
+            }
+            Ok(metadata) => {
+                entries.insert(relative.clone(), Entry { path: relative, local: path.clone(), is_dir: false, size: metadata.len(), mode: None });
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                deleted.insert(relative);
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(Changes { entries: entries.into_values().collect(), deleted: deleted.into_iter().collect() })
+}
+
+/// 列出服务器上被删除的路径：被删除的文件本身和被删除的目录中的所有文件，`dirs` 为 true 时列出这些目录
+///
+/// 输出和 [mirror::remote_files_command] 相同，服务器上不存在的路径会被忽略
+pub fn list_command(remote_dir: &str, deleted: &[String], dirs: bool) -> String {
+    let paths = deleted.iter().map(|path| format!("./{}", path)).collect::<Vec<_>>();
+    let kind = if dirs { "-type d" } else { "! -type d" };
+    format!("cd {} && {{ find {} {} 2>/dev/null || true; }}", quote(remote_dir), mirror::quote_all(&paths), kind)
+}
+
+/// 删除服务器上本地已经不存在的文件和目录，`files` 和 `dirs` 是 [list_command] 列出的路径
+///
+/// 不直接删除整个目录：先删除没有被排除和保护的文件，再由深到浅删除变空的目录，
+/// 被删除的目录中受保护的文件和它们所在的目录会保留下来
+pub fn delete_commands(remote_dir: &str, files: &[String], dirs: &[String], excludes: &[String], protected: &[String]) -> Vec<String> {
+    let files = mirror::deletions(files, &[], excludes, protected);
+    let mut dirs = dirs.iter()
+        .filter(|dir| !util::is_excluded(protected, dir))
+        .collect::<Vec<_>>();
+    dirs.sort_by_key(|dir| Reverse(dir.matches('/').count()));
+
+    let mut commands = files.chunks(BATCH_SIZE)
+        .map(|files| format!("cd {} && rm -f -- {}", quote(remote_dir), mirror::quote_all(files)))
+        .collect::<Vec<_>>();
+    commands.extend(dirs.chunks(BATCH_SIZE).map(|dirs| {
+        format!("cd {} && rmdir --ignore-fail-on-non-empty -- {}", quote(remote_dir), mirror::quote_all(dirs))
+    }));
+    commands
+}
+
+/// `path` 相对于 `dir` 的路径，以 `/` 分隔，不在 `dir` 中时返回 None
+fn relative_path(dir: &Path, path: &Path) -> Option<String> {
+    let parts = path.strip_prefix(dir).ok()?
+        .components()
+        .map(|component| match component {
+            Component::Normal(part) => Some(part.to_string_lossy().to_string()),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+    if parts.is_empty() {
+        return None;
+    }
+    Some(parts.join("/"))
+}
+
+#[test]
+fn test_changes() {
+    use std::env;
+    use std::process;
+
+    let root = env::temp_dir().join(format!("rpush-test-watch-{}", process::id()));
+    fs::create_dir_all(root.join("css")).unwrap();
+    fs::create_dir_all(root.join("img/icons")).unwrap();
+    fs::write(root.join("css/app.css"), b"body{}").unwrap();
+    fs::write(root.join("css/app.css.map"), b"{}").unwrap();
+    fs::write(root.join("img/icons/logo.svg"), b"<svg/>").unwrap();
+
+    let paths = [
+        root.join("css/app.css"),
+        root.join("css/app.css.map"),
+        root.join("img"),
+        root.join("img/icons/logo.svg"),
+        root.join("old.html"),
+        root.join(revision::FILE_NAME),
+        env::temp_dir().join("outside.txt"),
+    ];
+    let changes = changes(&root, &paths, &["*.map".to_string()]).unwrap();
+    fs::remove_dir_all(&root).unwrap();
+
+    let entries = changes.entries.iter().map(|entry| entry.path.as_str()).collect::<Vec<_>>();
+    assert_eq!(entries, vec!["css/app.css", "img", "img/icons", "img/icons/logo.svg"]);
+    assert_eq!(changes.entries[0].size, 6);
+    assert_eq!(changes.deleted, vec!["old.html"]);
+    assert_eq!(list_command("/var/www", &changes.deleted, false), "cd '/var/www' && { find './old.html' ! -type d 2>/dev/null || true; }");
+}
+
+#[test]
+fn test_delete_commands() {
+    // 本地删除了 uploads 目录，服务器上的 uploads/avatars/ 受保护
+    let files = mirror::parse_remote_files("./uploads/a.png
+./uploads/avatars/1.png
+./uploads/tmp/b.log
+");
+    let dirs = mirror::parse_remote_files("./uploads
+./uploads/avatars
+./uploads/tmp
+");
+    let commands = delete_commands("/var/www", &files, &dirs, &["*.log".to_string()], &["uploads/avatars/".to_string()]);
+    assert_eq!(commands, vec![
+        "cd '/var/www' && rm -f -- 'uploads/a.png'",
+        "cd '/var/www' && rmdir --ignore-fail-on-non-empty -- 'uploads/tmp' 'uploads'",
+    ]);
+}